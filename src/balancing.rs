@@ -1,9 +1,37 @@
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::fmt;
 
 use crate::debt::Debt;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+mod cash_flow;
+mod fees;
+mod hub;
+mod largest_transfer;
+mod restricted;
+mod strategy;
+mod transfer_limits;
+mod verify;
+
+pub use cash_flow::{balance_by_min_cash_flow, MIN_CASH_FLOW_EXACT_LEN};
+pub use fees::{
+    balance_by_min_fees, price_transactions, Fee, FeeModel, PricedTransaction, MIN_FEES_EXACT_LEN,
+};
+pub use hub::{balance_by_hub, choose_hub, HubChoice};
+pub use largest_transfer::{
+    balance_by_min_largest_transfer, CappedTransactions, MIN_LARGEST_TRANSFER_EXACT_LEN,
+};
+pub use restricted::{balance_by_restricted_edges, PaymentEdges, UnsettleableError};
+pub use strategy::{
+    BalancingStrategy, DebtedAmountsAsc, DebtedAmountsDesc, MinCashFlow, MinFees,
+    MinLargestTransfer, SpokeHub, StrategyRegistry, Treasurer, DEFAULT_STRATEGY,
+};
+pub use transfer_limits::{
+    balance_with_transfer_limits, TransferLimitError, TransferLimits, TRANSFER_LIMITS_EXACT_LEN,
+};
+pub use verify::{verify_settlement, SettlementError};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
     pub source: String,
     pub destination: String,
@@ -131,28 +159,51 @@ pub fn balance_by_debted_amounts_asc(debts: &[&Debt]) -> Vec<Transaction> {
 }
 
 /// Creates transactions from all debtors to a single person (the hub), then
-/// from that person to all creditors.
-pub fn balance_by_spoke_hub(debts: &[&Debt], hub_index: usize) -> Vec<Transaction> {
-    let hub = debts[hub_index];
-    debts[..hub_index]
-        .iter()
-        .chain(debts[(hub_index + 1)..].iter())
+/// from that person to all creditors, or returns `None` if there is no
+/// person at `hub_index`. See [`balance_by_hub`] to choose the hub.
+pub fn balance_by_spoke_hub(debts: &[&Debt], hub_index: usize) -> Option<Vec<Transaction>> {
+    let hub = debts.get(hub_index)?;
+    Some(pay_through_hub(
+        debts[..hub_index]
+            .iter()
+            .chain(debts[(hub_index + 1)..].iter()),
+        &hub.name,
+    ))
+}
+
+/// Creates transactions from each of the debtors to the hub, then from the
+/// hub to each of the creditors.
+fn pay_through_hub<'a>(spokes: impl Iterator<Item = &'a &'a Debt>, hub: &str) -> Vec<Transaction> {
+    spokes
         .flat_map(|debt| match debt.value.cmp(&0) {
             Ordering::Less => Some(Transaction {
-                source: hub.name.clone(),
+                source: hub.to_string(),
                 destination: debt.name.clone(),
                 value: (-debt.value) as u32,
             }),
             Ordering::Equal => None,
             Ordering::Greater => Some(Transaction {
                 source: debt.name.clone(),
-                destination: hub.name.clone(),
+                destination: hub.to_string(),
                 value: debt.value as u32,
             }),
         })
         .collect()
 }
 
+/// Debts of the values named a, b, c and so on, for tests.
+#[cfg(test)]
+pub(crate) fn debts(values: &[i32]) -> Vec<Debt> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| Debt {
+            name: ((b'a' + i as u8) as char).to_string(),
+            value,
+        })
+        .collect()
+}
+
 mod tests {
     use super::*;
 
@@ -219,7 +270,7 @@ mod tests {
             })
             .collect();
         let partition: Vec<_> = debts.iter().collect();
-        let transactions = balance_by_spoke_hub(&partition, 6);
+        let transactions = balance_by_spoke_hub(&partition, 6).unwrap();
         assert_eq!(
             transactions,
             [
@@ -231,6 +282,7 @@ mod tests {
                 Transaction::from("g", "f", 1340),
                 Transaction::from("g", "h", 3260),
             ]
-        )
+        );
+        assert_eq!(balance_by_spoke_hub(&partition, 8), None);
     }
 }
@@ -0,0 +1,116 @@
+use super::Transaction;
+use crate::debt::Debt;
+use crate::partitionings::ZeroSumPartitioningIndices;
+
+/// Largest group that is split into its most zero sum partitions before
+/// settling, so that the fewest transactions are exact.
+pub const MIN_CASH_FLOW_EXACT_LEN: usize = 16;
+
+/// Creates the fewest transactions that move the least money in total.
+///
+/// Each debtor must pay out at least what they owe, so no settlement moves
+/// less than the sum of the debts. Paying debtors straight to creditors, and
+/// settling at least one person with each transaction, moves exactly that.
+/// Doing so separately for each zero sum partition of the group also gives
+/// the fewest transactions, which is exact for groups of up to
+/// [`MIN_CASH_FLOW_EXACT_LEN`] people and otherwise left to how the group was
+/// partitioned.
+pub fn balance_by_min_cash_flow(debts: &[&Debt]) -> Vec<Transaction> {
+    let values: Vec<_> = debts.iter().map(|debt| debt.value).collect();
+    if debts.len() <= MIN_CASH_FLOW_EXACT_LEN {
+        if let Some(partitioning) = ZeroSumPartitioningIndices::new(&values)
+            .ok()
+            .and_then(|mut partitionings| partitionings.next())
+        {
+            return partitioning
+                .into_iter()
+                .flat_map(|partition| {
+                    let partition: Vec<_> = partition.into_iter().map(|i| debts[i]).collect();
+                    pay_creditors_directly(&partition)
+                })
+                .collect();
+        }
+    }
+    pay_creditors_directly(debts)
+}
+
+/// Pays the largest remaining debt to the largest remaining credit until one
+/// of them is settled, never passing money through a third person.
+pub(super) fn pay_creditors_directly(debts: &[&Debt]) -> Vec<Transaction> {
+    let mut debtors: Vec<_> = debts
+        .iter()
+        .filter(|debt| debt.value > 0)
+        .map(|debt| (debt.name.as_str(), debt.value as u32))
+        .collect();
+    let mut creditors: Vec<_> = debts
+        .iter()
+        .filter(|debt| debt.value < 0)
+        .map(|debt| (debt.name.as_str(), debt.value.unsigned_abs()))
+        .collect();
+    // Stable sorts so that equal amounts are paid in the order given.
+    debtors.sort_by_key(|&(_, value)| std::cmp::Reverse(value));
+    creditors.sort_by_key(|&(_, value)| std::cmp::Reverse(value));
+
+    let mut transactions = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < debtors.len() && j < creditors.len() {
+        let value = debtors[i].1.min(creditors[j].1);
+        transactions.push(Transaction::from(debtors[i].0, creditors[j].0, value));
+        debtors[i].1 -= value;
+        creditors[j].1 -= value;
+        if debtors[i].1 == 0 {
+            i += 1;
+        }
+        if creditors[j].1 == 0 {
+            j += 1;
+        }
+    }
+    transactions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+
+    #[test]
+    fn test_balance_by_min_cash_flow() {
+        let debts = debts(&[4000, 2000, 1090, 1000, -1080, -1340, -2410, -3260]);
+        let partition: Vec<_> = debts.iter().collect();
+        let transactions = balance_by_min_cash_flow(&partition);
+        // The ascending balancer moves 13760 with as many transactions.
+        assert_eq!(
+            transactions,
+            [
+                Transaction::from("a", "h", 3260),
+                Transaction::from("a", "g", 740),
+                Transaction::from("b", "g", 1670),
+                Transaction::from("b", "f", 330),
+                Transaction::from("c", "f", 1010),
+                Transaction::from("c", "e", 80),
+                Transaction::from("d", "e", 1000),
+            ]
+        );
+        assert_eq!(
+            transactions.iter().map(|t| t.value).sum::<u32>(),
+            4000 + 2000 + 1090 + 1000
+        );
+    }
+
+    #[test]
+    fn test_balance_by_min_cash_flow_splits_zero_sum_groups() {
+        // Paying the largest amounts first takes four transactions, but b and
+        // d can settle between themselves.
+        let debts = debts(&[500, 300, -400, -300, -100]);
+        let partition: Vec<_> = debts.iter().collect();
+        assert_eq!(
+            balance_by_min_cash_flow(&partition),
+            [
+                Transaction::from("a", "c", 400),
+                Transaction::from("a", "e", 100),
+                Transaction::from("b", "d", 300),
+            ]
+        );
+        assert_eq!(pay_creditors_directly(&partition).len(), 4);
+    }
+}
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::cash_flow::pay_creditors_directly;
+use super::largest_transfer::search_direct_payments;
+use super::Transaction;
+use crate::debt::Debt;
+
+/// Largest number of people with outstanding debts for which every way of
+/// paying debtors straight to creditors is searched for the least fees.
+pub const MIN_FEES_EXACT_LEN: usize = 8;
+
+/// Cost of a transfer, charged to the payer on top of what they send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Fee {
+    /// Charged on every transfer, in cents.
+    pub fixed: u32,
+    /// Charged per 10000 cents sent, rounded up. Must be less than 10000.
+    pub basis_points: u32,
+}
+
+impl Fee {
+    /// Returns the least amount to send for `received` to arrive once the
+    /// fee is taken out.
+    pub fn sent(&self, received: u32) -> u64 {
+        let basis_points = self.basis_points.min(9999) as u64;
+        let net = |sent: u64| sent - self.fixed as u64 - (sent * basis_points + 9999) / 10000;
+        let mut sent = ((received as u64 + self.fixed as u64) * 10000 + 9999 - basis_points)
+            / (10000 - basis_points);
+        while net(sent) < received as u64 {
+            sent += 1;
+        }
+        sent
+    }
+
+    /// Returns the fee on a transfer where `received` arrives.
+    pub fn charge(&self, received: u32) -> u64 {
+        self.sent(received) - received as u64
+    }
+}
+
+/// Fees of the transfer methods available between people.
+///
+/// A fee set for a payer and payee pair takes precedence over one set for
+/// the payer, which takes precedence over the global one.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FeeModel {
+    pub global: Fee,
+    pub people: HashMap<String, Fee>,
+    #[serde(with = "pair_fees")]
+    pub pairs: HashMap<(String, String), Fee>,
+}
+
+impl FeeModel {
+    pub fn fee(&self, source: &str, destination: &str) -> Fee {
+        self.pairs
+            .get(&(source.to_string(), destination.to_string()))
+            .or_else(|| self.people.get(source))
+            .copied()
+            .unwrap_or(self.global)
+    }
+}
+
+/// Serializes fees keyed by payer and payee as a list, since formats such as
+/// JSON only have string keys.
+mod pair_fees {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        pairs: &HashMap<(String, String), Fee>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut pairs: Vec<_> = pairs.iter().collect();
+        pairs.sort_by_key(|&(pair, _)| pair);
+        serializer.collect_seq(pairs)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<(String, String), Fee>, D::Error> {
+        Ok(Vec::<((String, String), Fee)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Transaction along with the fee its payer pays on top of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PricedTransaction {
+    /// What the recipient receives.
+    pub transaction: Transaction,
+    pub fee: u64,
+}
+
+impl PricedTransaction {
+    /// Returns what the payer must send.
+    pub fn sent(&self) -> u64 {
+        self.transaction.value as u64 + self.fee
+    }
+}
+
+impl fmt::Display for PricedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sends ${}.{:02} to {}, who receives ${}.{:02} after ${}.{:02} in fees",
+            self.transaction.source,
+            self.sent() / 100,
+            self.sent() % 100,
+            self.transaction.destination,
+            self.transaction.value / 100,
+            self.transaction.value % 100,
+            self.fee / 100,
+            self.fee % 100,
+        )
+    }
+}
+
+/// Adds the fee of each transaction, so that its recipient still receives
+/// its value.
+pub fn price_transactions(transactions: &[Transaction], fees: &FeeModel) -> Vec<PricedTransaction> {
+    transactions
+        .iter()
+        .map(|transaction| PricedTransaction {
+            fee: fees
+                .fee(&transaction.source, &transaction.destination)
+                .charge(transaction.value),
+            transaction: transaction.clone(),
+        })
+        .collect()
+}
+
+/// Creates transactions paying debtors straight to creditors with the least
+/// fees in total, and the fewest transactions among those.
+///
+/// For up to [`MIN_FEES_EXACT_LEN`] people every order of paying a debtor to a
+/// creditor until one of them is settled is searched, as for
+/// [`balance_by_min_largest_transfer`](super::balance_by_min_largest_transfer).
+/// Larger groups pay the largest debts to the largest credits.
+pub fn balance_by_min_fees(debts: &[&Debt], fees: &FeeModel) -> Vec<PricedTransaction> {
+    let people: Vec<_> = debts
+        .iter()
+        .copied()
+        .filter(|debt| debt.value != 0)
+        .collect();
+    let transactions = if people.len() <= MIN_FEES_EXACT_LEN {
+        search_direct_payments(&people, (0, 0), |(total, len), debtor, creditor, value| {
+            let fee = fees.fee(&people[debtor].name, &people[creditor].name);
+            (total + fee.charge(value), len + 1)
+        })
+    } else {
+        None
+    }
+    .unwrap_or_else(|| pay_creditors_directly(debts));
+    price_transactions(&transactions, fees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+
+    #[test]
+    fn test_fee_sent() {
+        let fee = Fee {
+            fixed: 30,
+            basis_points: 290,
+        };
+        // 10330 less 30 and 2.9% of it rounded up is 10000.
+        assert_eq!(fee.sent(10000), 10330);
+        assert_eq!(fee.charge(10000), 330);
+        assert_eq!(Fee::default().sent(10000), 10000);
+    }
+
+    #[test]
+    fn test_balance_by_min_fees() {
+        let debts = debts(&[500, 300, -400, -300, -100]);
+        let partition: Vec<_> = debts.iter().collect();
+        let mut fees = FeeModel {
+            global: Fee {
+                fixed: 10,
+                basis_points: 0,
+            },
+            ..Default::default()
+        };
+        // Sending from b is free, so b pays all it can.
+        fees.people.insert(
+            "b".to_string(),
+            Fee {
+                fixed: 0,
+                basis_points: 0,
+            },
+        );
+        fees.pairs.insert(
+            ("a".to_string(), "c".to_string()),
+            Fee {
+                fixed: 0,
+                basis_points: 1000,
+            },
+        );
+        let transactions = balance_by_min_fees(&partition, &fees);
+        assert_eq!(
+            transactions,
+            [
+                PricedTransaction {
+                    transaction: Transaction::from("a", "d", 300),
+                    fee: 10,
+                },
+                PricedTransaction {
+                    transaction: Transaction::from("a", "e", 100),
+                    fee: 10,
+                },
+                PricedTransaction {
+                    transaction: Transaction::from("a", "c", 100),
+                    fee: 12,
+                },
+                PricedTransaction {
+                    transaction: Transaction::from("b", "c", 300),
+                    fee: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            transactions[2].to_string(),
+            "a sends $1.12 to c, who receives $1.00 after $0.12 in fees"
+        );
+    }
+}
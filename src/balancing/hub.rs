@@ -0,0 +1,89 @@
+use std::cmp::Reverse;
+
+use super::{balance_by_spoke_hub, pay_through_hub, Transaction};
+use crate::debt::Debt;
+
+/// How the hub that everyone pays through is chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HubChoice {
+    /// The person owed the most.
+    LargestBalance,
+    /// The person that leaves the least money to move, which is the one with
+    /// the largest amount owing or owed as every other amount passes through
+    /// them. Any hub with an outstanding debt leaves the same number of
+    /// transfers, one for each other such person.
+    LeastMoneyMoved,
+    /// The person with this name, even if they aren't among the debts.
+    Treasurer(String),
+}
+
+/// Returns the index of the hub among the debts, or `None` if there is no
+/// such person. The first of equally good people is chosen.
+pub fn choose_hub(debts: &[&Debt], choice: &HubChoice) -> Option<usize> {
+    match choice {
+        HubChoice::LargestBalance => (0..debts.len())
+            .filter(|&i| debts[i].value < 0)
+            .min_by_key(|&i| debts[i].value),
+        HubChoice::LeastMoneyMoved => {
+            (0..debts.len()).min_by_key(|&i| Reverse(debts[i].value.unsigned_abs()))
+        }
+        HubChoice::Treasurer(name) => debts.iter().position(|debt| debt.name == *name),
+    }
+}
+
+/// Creates transactions through the chosen hub, see
+/// [`balance_by_spoke_hub`]. A treasurer without debts of their own is added
+/// as the hub, and otherwise no transactions are created if nobody can be
+/// the hub.
+pub fn balance_by_hub(debts: &[&Debt], choice: &HubChoice) -> Vec<Transaction> {
+    match (choose_hub(debts, choice), choice) {
+        (Some(hub_index), _) => balance_by_spoke_hub(debts, hub_index).unwrap_or_default(),
+        (None, HubChoice::Treasurer(name)) => pay_through_hub(debts.iter(), name),
+        (None, _) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+
+    #[test]
+    fn test_choose_hub() {
+        let debts = debts(&[4000, 0, 1090, -1080, -3260, -750]);
+        let partition: Vec<_> = debts.iter().collect();
+        assert_eq!(choose_hub(&partition, &HubChoice::LargestBalance), Some(4));
+        assert_eq!(choose_hub(&partition, &HubChoice::LeastMoneyMoved), Some(0));
+        assert_eq!(
+            choose_hub(&partition, &HubChoice::Treasurer("b".to_string())),
+            Some(1)
+        );
+        assert_eq!(
+            choose_hub(&partition, &HubChoice::Treasurer("z".to_string())),
+            None
+        );
+        assert_eq!(choose_hub(&[], &HubChoice::LeastMoneyMoved), None);
+    }
+
+    #[test]
+    fn test_balance_by_hub() {
+        let debts = debts(&[300, -100, -200]);
+        let partition: Vec<_> = debts.iter().collect();
+        assert_eq!(
+            balance_by_hub(&partition, &HubChoice::LargestBalance),
+            [
+                Transaction::from("a", "c", 300),
+                Transaction::from("c", "b", 100),
+            ]
+        );
+        // A treasurer from outside the group passes on every payment.
+        assert_eq!(
+            balance_by_hub(&partition, &HubChoice::Treasurer("t".to_string())),
+            [
+                Transaction::from("a", "t", 300),
+                Transaction::from("t", "b", 100),
+                Transaction::from("t", "c", 200),
+            ]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use super::cash_flow::pay_creditors_directly;
+use super::{balance_by_min_cash_flow, Transaction};
+use crate::debt::Debt;
+
+/// Largest number of people with outstanding debts for which every way of
+/// paying debtors straight to creditors is searched.
+pub const MIN_LARGEST_TRANSFER_EXACT_LEN: usize = 8;
+
+/// Transactions that keep every transfer under a cap.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CappedTransactions {
+    pub transactions: Vec<Transaction>,
+    /// Number of transactions beyond the fewest that settle the debts
+    /// without a cap, see [`balance_by_min_cash_flow`].
+    pub extra_len: usize,
+}
+
+/// Creates transactions paying debtors straight to creditors with the
+/// smallest largest transfer, and the fewest transactions among those.
+///
+/// For up to [`MIN_LARGEST_TRANSFER_EXACT_LEN`] people every order of paying
+/// a debtor to a creditor until one of them is settled is searched, sharing
+/// the results for equal remaining amounts. Settlements where a payment
+/// settles nobody, such as splitting a debt between several payments to the
+/// same person, aren't searched, as that is what the cap is for. Larger
+/// groups pay the largest debts to the largest credits.
+///
+/// With a `cap`, the settlement is instead the one with the fewest payments
+/// once transfers over the cap are split into payments of at most `cap`.
+pub fn balance_by_min_largest_transfer(debts: &[&Debt], cap: Option<u32>) -> CappedTransactions {
+    let people: Vec<_> = debts
+        .iter()
+        .copied()
+        .filter(|debt| debt.value != 0)
+        .collect();
+    let transactions = if people.len() <= MIN_LARGEST_TRANSFER_EXACT_LEN {
+        match cap {
+            // Fewest payments once split at the cap, then smallest largest
+            // transfer.
+            Some(cap) => {
+                let cap = cap.max(1);
+                search_direct_payments(&people, (0, 0), |(len, largest), _, _, value| {
+                    let split_len = (value as u64 + cap as u64 - 1) / cap as u64;
+                    (len + split_len as usize, value.max(largest))
+                })
+            }
+            // Smallest largest transfer, then fewest transactions.
+            None => search_direct_payments(&people, (0, 0), |(largest, len), _, _, value| {
+                (value.max(largest), len + 1)
+            }),
+        }
+    } else {
+        None
+    }
+    .unwrap_or_else(|| pay_creditors_directly(debts));
+    let transactions = match cap {
+        Some(cap) => split_at_cap(transactions, cap),
+        None => transactions,
+    };
+    CappedTransactions {
+        extra_len: transactions
+            .len()
+            .saturating_sub(balance_by_min_cash_flow(debts).len()),
+        transactions,
+    }
+}
+
+/// Splits transactions over the cap into as few payments of at most the cap
+/// as possible.
+fn split_at_cap(transactions: Vec<Transaction>, cap: u32) -> Vec<Transaction> {
+    let cap = cap.max(1);
+    transactions
+        .into_iter()
+        .flat_map(|transaction| {
+            let full_len = transaction.value / cap;
+            let rest = transaction.value % cap;
+            let values = std::iter::repeat(cap)
+                .take(full_len as usize)
+                .chain((rest > 0).then_some(rest));
+            values
+                .map(|value| Transaction {
+                    value,
+                    ..transaction.clone()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Searches every order of paying a debtor straight to a creditor until one
+/// of them is settled for the settlement with the least cost, sharing the
+/// results for equal remaining amounts. `pay` adds a payment from one person
+/// to another to the cost of the payments after it, starting from `settled`.
+/// Returns `None` if the people don't sum to zero.
+pub(super) fn search_direct_payments<C>(
+    people: &[&Debt],
+    settled: C,
+    pay: impl Fn(C, usize, usize, u32) -> C,
+) -> Option<Vec<Transaction>>
+where
+    C: Copy + Ord,
+{
+    let mut remaining: Vec<_> = people.iter().map(|debt| debt.value as i64).collect();
+    let mut memo = HashMap::new();
+    least_cost(&mut remaining, &mut memo, settled, &pay)?;
+
+    // Follow the payments that lead to the cheapest settlement.
+    let mut transactions = vec![];
+    while let Some(cost) = memo.get(&remaining).copied().flatten() {
+        if remaining.iter().all(|&value| value == 0) {
+            break;
+        }
+        let (debtor, creditor, value) = payments(&remaining)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .find(|&(debtor, creditor, value)| {
+                remaining[debtor] -= value;
+                remaining[creditor] += value;
+                let next = memo.get(&remaining).copied().flatten();
+                remaining[debtor] += value;
+                remaining[creditor] -= value;
+                next.map(|next| pay(next, debtor, creditor, value as u32)) == Some(cost)
+            })
+            .unwrap();
+        remaining[debtor] -= value;
+        remaining[creditor] += value;
+        transactions.push(Transaction::from(
+            &people[debtor].name,
+            &people[creditor].name,
+            value as u32,
+        ));
+    }
+    Some(transactions)
+}
+
+/// Least cost of settling the remaining amounts, or `None` if they can't be
+/// settled.
+fn least_cost<C>(
+    remaining: &mut Vec<i64>,
+    memo: &mut HashMap<Vec<i64>, Option<C>>,
+    settled: C,
+    pay: &impl Fn(C, usize, usize, u32) -> C,
+) -> Option<C>
+where
+    C: Copy + Ord,
+{
+    if let Some(&cost) = memo.get(remaining) {
+        return cost;
+    }
+    if remaining.iter().all(|&value| value == 0) {
+        memo.insert(remaining.clone(), Some(settled));
+        return Some(settled);
+    }
+    let mut least = None;
+    for (debtor, creditor, value) in payments(remaining).collect::<Vec<_>>() {
+        remaining[debtor] -= value;
+        remaining[creditor] += value;
+        let next = least_cost(remaining, memo, settled, pay);
+        remaining[debtor] += value;
+        remaining[creditor] -= value;
+        if let Some(next) = next {
+            let candidate = pay(next, debtor, creditor, value as u32);
+            if least.map_or(true, |least| candidate < least) {
+                least = Some(candidate);
+            }
+        }
+    }
+    memo.insert(remaining.clone(), least);
+    least
+}
+
+/// Payments from each remaining debtor to each remaining creditor that settle
+/// at least one of them.
+pub(super) fn payments(remaining: &[i64]) -> impl Iterator<Item = (usize, usize, i64)> + '_ {
+    (0..remaining.len())
+        .filter(|&debtor| remaining[debtor] > 0)
+        .flat_map(move |debtor| {
+            (0..remaining.len())
+                .filter(|&creditor| remaining[creditor] < 0)
+                .map(move |creditor| {
+                    (
+                        debtor,
+                        creditor,
+                        remaining[debtor].min(-remaining[creditor]),
+                    )
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+
+    #[test]
+    fn test_balance_by_min_largest_transfer() {
+        let debts = debts(&[4000, 2000, 1090, 1000, -1080, -1340, -2410, -3260]);
+        let partition: Vec<_> = debts.iter().collect();
+        let settlement = balance_by_min_largest_transfer(&partition, None);
+        // Paying the largest debts to the largest credits transfers 3260.
+        assert_eq!(
+            settlement.transactions,
+            [
+                Transaction::from("a", "f", 1340),
+                Transaction::from("b", "e", 1080),
+                Transaction::from("b", "h", 920),
+                Transaction::from("c", "g", 1090),
+                Transaction::from("a", "g", 1320),
+                Transaction::from("a", "h", 1340),
+                Transaction::from("d", "h", 1000),
+            ]
+        );
+        assert_eq!(settlement.extra_len, 0);
+        assert_eq!(
+            pay_creditors_directly(&partition)
+                .iter()
+                .map(|t| t.value)
+                .max(),
+            Some(3260)
+        );
+    }
+
+    #[test]
+    fn test_balance_by_min_largest_transfer_with_cap() {
+        let debts = debts(&[500, 100, -300, -300]);
+        let partition: Vec<_> = debts.iter().collect();
+        assert_eq!(
+            balance_by_min_largest_transfer(&partition, Some(250)),
+            CappedTransactions {
+                transactions: vec![
+                    Transaction::from("a", "c", 250),
+                    Transaction::from("a", "c", 50),
+                    Transaction::from("a", "d", 200),
+                    Transaction::from("b", "d", 100),
+                ],
+                extra_len: 1,
+            }
+        );
+        assert_eq!(
+            balance_by_min_largest_transfer(&partition, Some(100))
+                .transactions
+                .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_balance_by_min_largest_transfer_fewest_split() {
+        let debts = debts(&[400, 100, -300, -200]);
+        let partition: Vec<_> = debts.iter().collect();
+        // a paying 200 to both c and d is the smallest largest transfer, but
+        // splits into five payments of at most 150 rather than four.
+        assert_eq!(
+            balance_by_min_largest_transfer(&partition, None).transactions,
+            [
+                Transaction::from("a", "d", 200),
+                Transaction::from("a", "c", 200),
+                Transaction::from("b", "c", 100),
+            ]
+        );
+        assert_eq!(
+            balance_by_min_largest_transfer(&partition, Some(150)),
+            CappedTransactions {
+                transactions: vec![
+                    Transaction::from("a", "c", 150),
+                    Transaction::from("a", "c", 150),
+                    Transaction::from("a", "d", 100),
+                    Transaction::from("b", "d", 100),
+                ],
+                extra_len: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_balance_by_min_largest_transfer_extra_len() {
+        let debts = debts(&[100, -100, 200, -200]);
+        let partition: Vec<_> = debts.iter().collect();
+        // a paying b and c paying d takes two transactions, or three once c's
+        // payment is split at the cap, as do three payments of 100 without
+        // splitting. Either is one more than without a cap.
+        assert_eq!(balance_by_min_cash_flow(&partition).len(), 2);
+        assert_eq!(
+            balance_by_min_largest_transfer(&partition, Some(150)),
+            CappedTransactions {
+                transactions: vec![
+                    Transaction::from("a", "d", 100),
+                    Transaction::from("c", "b", 100),
+                    Transaction::from("c", "d", 100),
+                ],
+                extra_len: 1,
+            }
+        );
+    }
+}
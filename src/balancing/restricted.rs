@@ -0,0 +1,409 @@
+use std::collections::{HashSet, VecDeque};
+use std::error;
+use std::fmt;
+
+use super::{Transaction, MIN_CASH_FLOW_EXACT_LEN};
+use crate::debt::Debt;
+use crate::partitionings::ZeroSumPartitioningIndices;
+
+/// Which payers can pay which payees, by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentEdges {
+    /// Only these payer to payee pairs can be used.
+    Allowed(HashSet<(String, String)>),
+    /// Any pair except these can be used.
+    Forbidden(HashSet<(String, String)>),
+}
+
+impl PaymentEdges {
+    pub fn allows(&self, source: &str, destination: &str) -> bool {
+        let edge = (source.to_string(), destination.to_string());
+        match self {
+            Self::Allowed(edges) => edges.contains(&edge),
+            Self::Forbidden(edges) => !edges.contains(&edge),
+        }
+    }
+}
+
+impl Default for PaymentEdges {
+    /// Allows every payer to pay every payee.
+    fn default() -> Self {
+        Self::Forbidden(HashSet::new())
+    }
+}
+
+/// No settlement uses only the allowed payments, because `debtors` owe
+/// `shortfall` more than the `creditors` they can reach are owed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsettleableError {
+    pub debtors: Vec<String>,
+    pub creditors: Vec<String>,
+    pub shortfall: u32,
+}
+
+impl fmt::Display for UnsettleableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} owe ${}.{:02} more than ",
+            self.debtors.join(", "),
+            self.shortfall / 100,
+            self.shortfall % 100,
+        )?;
+        if self.creditors.is_empty() {
+            write!(f, "anyone they can pay is owed")
+        } else {
+            write!(f, "{} can be paid", self.creditors.join(", "))
+        }
+    }
+}
+
+impl error::Error for UnsettleableError {}
+
+/// Creates transactions using only the allowed payments, or returns which
+/// debtors can't pay what they owe.
+///
+/// The debts are settled as a flow from debtors to creditors, which may pass
+/// money through people who are allowed to receive and pay it on. Payments
+/// are then moved around every cycle of payers and payees until one of them
+/// is dropped, leaving at most one fewer transaction than people. As with
+/// [`balance_by_min_cash_flow`](super::balance_by_min_cash_flow), each zero
+/// sum partition of a group of up to [`MIN_CASH_FLOW_EXACT_LEN`] people is
+/// settled separately, for fewer transactions, using the first partitioning
+/// with the most partitions whose partitions can all be settled.
+pub fn balance_by_restricted_edges(
+    debts: &[&Debt],
+    edges: &PaymentEdges,
+) -> Result<Vec<Transaction>, UnsettleableError> {
+    if debts.len() <= MIN_CASH_FLOW_EXACT_LEN {
+        let values: Vec<_> = debts.iter().map(|debt| debt.value).collect();
+        // The allowed payments may split one partitioning and not another
+        // with as many partitions, so each is tried in turn.
+        let settled = ZeroSumPartitioningIndices::new(&values)
+            .into_iter()
+            .flatten()
+            .find_map(|partitioning| {
+                partitioning
+                    .into_iter()
+                    .map(|partition| {
+                        let partition: Vec<_> = partition.into_iter().map(|i| debts[i]).collect();
+                        settle_by_flow(&partition, edges).ok()
+                    })
+                    .collect::<Option<Vec<_>>>()
+            });
+        if let Some(transactions) = settled {
+            return Ok(transactions.into_iter().flatten().collect());
+        }
+    }
+    settle_by_flow(debts, edges)
+}
+
+/// Payment from one person to another, by index, with the amount paid.
+struct Edge {
+    source: usize,
+    destination: usize,
+    flow: i64,
+}
+
+fn settle_by_flow(
+    debts: &[&Debt],
+    edges: &PaymentEdges,
+) -> Result<Vec<Transaction>, UnsettleableError> {
+    let mut flows = max_flow(debts, edges)?;
+    drop_cycles(&mut flows);
+    Ok(flows
+        .into_iter()
+        .filter(|edge| edge.flow > 0)
+        .map(|edge| {
+            Transaction::from(
+                &debts[edge.source].name,
+                &debts[edge.destination].name,
+                edge.flow as u32,
+            )
+        })
+        .collect())
+}
+
+/// Pays every debt along shortest paths of allowed payments, returning the
+/// amount paid over each of them.
+fn max_flow(debts: &[&Debt], edges: &PaymentEdges) -> Result<Vec<Edge>, UnsettleableError> {
+    let len = debts.len();
+    // The source is `len` and the sink `len + 1`. No payment between people
+    // needs to carry more than all of the debts, so only the debts and
+    // credits bound the flow.
+    let (source, sink) = (len, len + 1);
+    let total: i64 = debts.iter().map(|debt| (debt.value as i64).max(0)).sum();
+    let mut capacity = vec![vec![0i64; len + 2]; len + 2];
+    for (i, debt) in debts.iter().enumerate() {
+        if debt.value > 0 {
+            capacity[source][i] = debt.value as i64;
+        } else {
+            capacity[i][sink] = -(debt.value as i64);
+        }
+        for (j, other) in debts.iter().enumerate() {
+            if i != j && edges.allows(&debt.name, &other.name) {
+                capacity[i][j] = total;
+            }
+        }
+    }
+    // Net flow, so that `flow[v][u]` is always `-flow[u][v]` and paying both
+    // ways between two people cancels out.
+    let mut flow = vec![vec![0i64; len + 2]; len + 2];
+    let residual = |flow: &[Vec<i64>], u: usize, v: usize| capacity[u][v] - flow[u][v];
+
+    // Edmonds-Karp: augment along shortest paths until none is left.
+    loop {
+        let mut previous = vec![None; len + 2];
+        previous[source] = Some(source);
+        let mut queue = VecDeque::from([source]);
+        while let Some(u) = queue.pop_front() {
+            for (v, previous) in previous.iter_mut().enumerate() {
+                if previous.is_none() && residual(&flow, u, v) > 0 {
+                    *previous = Some(u);
+                    queue.push_back(v);
+                }
+            }
+        }
+        if previous[sink].is_none() {
+            let owed: Vec<_> = (0..len).map(|i| residual(&flow, source, i)).collect();
+            if let Some(error) = unsettled(debts, &owed, &previous) {
+                return Err(error);
+            }
+            return Ok((0..len)
+                .flat_map(|i| (0..len).map(move |j| (i, j)))
+                .filter(|&(i, j)| flow[i][j] > 0)
+                .map(|(i, j)| Edge {
+                    source: i,
+                    destination: j,
+                    flow: flow[i][j],
+                })
+                .collect());
+        }
+        let mut path = vec![];
+        let mut v = sink;
+        while v != source {
+            let u = previous[v].unwrap();
+            path.push((u, v));
+            v = u;
+        }
+        let augment = path
+            .iter()
+            .map(|&(u, v)| residual(&flow, u, v))
+            .min()
+            .unwrap();
+        for (u, v) in path {
+            flow[u][v] += augment;
+            flow[v][u] -= augment;
+        }
+    }
+}
+
+/// Returns the debtors still reachable from the source once the flow is
+/// maximal, if any of them owe more than they could pay.
+fn unsettled(
+    debts: &[&Debt],
+    owed: &[i64],
+    reachable: &[Option<usize>],
+) -> Option<UnsettleableError> {
+    let mut debtors = vec![];
+    let mut creditors = vec![];
+    let mut shortfall = 0;
+    for (i, debt) in debts.iter().enumerate() {
+        if reachable[i].is_none() {
+            continue;
+        }
+        if debt.value > 0 {
+            debtors.push(debt.name.clone());
+            shortfall += owed[i];
+        } else if debt.value < 0 {
+            creditors.push(debt.name.clone());
+        }
+    }
+    (shortfall > 0).then_some(UnsettleableError {
+        debtors,
+        creditors,
+        shortfall: shortfall as u32,
+    })
+}
+
+/// Moves payments around each cycle of people paying each other, ignoring
+/// directions, until one of its payments is dropped. This keeps what
+/// everyone pays and receives, so repeating it leaves at most one payment
+/// fewer than people.
+fn drop_cycles(edges: &mut Vec<Edge>) {
+    while let Some(cycle) = find_cycle(edges) {
+        // Payments along the cycle gain what those against it lose, so
+        // turning the cycle the way with fewer payments along it moves less
+        // money in total.
+        let (along, against): (Vec<_>, Vec<_>) = cycle.iter().partition(|&&(_, is_along)| is_along);
+        let (grow, shrink) = if along.len() <= against.len() && !against.is_empty() {
+            (along, against)
+        } else {
+            (against, along)
+        };
+        let flow = shrink.iter().map(|&&(i, _)| edges[i].flow).min().unwrap();
+        for &(i, _) in grow {
+            edges[i].flow += flow;
+        }
+        for &(i, _) in shrink {
+            edges[i].flow -= flow;
+        }
+        edges.retain(|edge| edge.flow > 0);
+    }
+}
+
+/// Finds a cycle of payments ignoring their directions, as the indices of
+/// the edges with whether each is walked from its source to its
+/// destination.
+fn find_cycle(edges: &[Edge]) -> Option<Vec<(usize, bool)>> {
+    let len = edges
+        .iter()
+        .map(|edge| edge.source.max(edge.destination) + 1)
+        .max()
+        .unwrap_or(0);
+    // Edges of a forest, added one at a time until one closes a cycle.
+    let mut forest: Vec<Vec<(usize, usize, bool)>> = vec![vec![]; len];
+    for (i, edge) in edges.iter().enumerate() {
+        if let Some(mut path) = forest_path(&forest, edge.destination, edge.source) {
+            path.push((i, true));
+            return Some(path);
+        }
+        forest[edge.source].push((edge.destination, i, true));
+        forest[edge.destination].push((edge.source, i, false));
+    }
+    None
+}
+
+/// Path between two people in the forest, as in [`find_cycle`].
+fn forest_path(
+    forest: &[Vec<(usize, usize, bool)>],
+    from: usize,
+    to: usize,
+) -> Option<Vec<(usize, bool)>> {
+    let mut previous = vec![None; forest.len()];
+    let mut queue = VecDeque::from([from]);
+    let mut visited = vec![false; forest.len()];
+    visited[from] = true;
+    while let Some(u) = queue.pop_front() {
+        if u == to {
+            let mut path = vec![];
+            let mut v = to;
+            while let Some((u, edge, is_along)) = previous[v] {
+                path.push((edge, is_along));
+                v = u;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &(v, edge, is_along) in &forest[u] {
+            if !visited[v] {
+                visited[v] = true;
+                previous[v] = Some((u, edge, is_along));
+                queue.push_back(v);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+    use crate::balancing::verify_settlement;
+
+    fn debts_named(debts: &[(&str, i32)]) -> Vec<Debt> {
+        debts
+            .iter()
+            .map(|&(name, value)| Debt {
+                name: name.to_string(),
+                value,
+            })
+            .collect()
+    }
+
+    fn edges(pairs: &[(&str, &str)]) -> HashSet<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(source, destination)| (source.to_string(), destination.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_balance_by_restricted_edges() {
+        let debts = debts(&[500, 300, -400, -300, -100]);
+        let partition: Vec<_> = debts.iter().collect();
+        // b can't pay d, so it pays c instead, and a pays the rest.
+        let transactions =
+            balance_by_restricted_edges(&partition, &PaymentEdges::Forbidden(edges(&[("b", "d")])))
+                .unwrap();
+        assert_eq!(transactions.len(), 4);
+        assert!(!transactions.contains(&Transaction::from("b", "d", 300)));
+        for debt in &debts {
+            let paid: i32 = transactions
+                .iter()
+                .map(|t| {
+                    if t.source == debt.name {
+                        t.value as i32
+                    } else if t.destination == debt.name {
+                        -(t.value as i32)
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            assert_eq!(paid, debt.value, "{}", debt.name);
+        }
+
+        // a can only pay c through b.
+        let debts = debts_named(&[("a", 200), ("b", 0), ("c", -200)]);
+        let partition: Vec<_> = debts.iter().collect();
+        assert_eq!(
+            balance_by_restricted_edges(
+                &partition,
+                &PaymentEdges::Allowed(edges(&[("a", "b"), ("b", "c")])),
+            ),
+            Ok(vec![
+                Transaction::from("a", "b", 200),
+                Transaction::from("b", "c", 200),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_balance_by_restricted_edges_both_ways() {
+        let debts = debts_named(&[("a", 100), ("b", -100), ("c", 50), ("d", -50)]);
+        let partition: Vec<_> = debts.iter().collect();
+        // c only reaches d through b and a, against a's payment to b.
+        let transactions = balance_by_restricted_edges(
+            &partition,
+            &PaymentEdges::Allowed(edges(&[("a", "b"), ("b", "a"), ("c", "b"), ("a", "d")])),
+        )
+        .unwrap();
+        let residuals = verify_settlement(&partition, &transactions).unwrap();
+        assert!(
+            residuals.iter().all(|debt| debt.value == 0),
+            "{transactions:?}"
+        );
+    }
+
+    #[test]
+    fn test_balance_by_restricted_edges_unsettleable() {
+        let debts = debts(&[500, 300, -400, -400]);
+        let partition: Vec<_> = debts.iter().collect();
+        let error = balance_by_restricted_edges(
+            &partition,
+            &PaymentEdges::Allowed(edges(&[("a", "c"), ("b", "c")])),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            UnsettleableError {
+                debtors: vec!["a".to_string(), "b".to_string()],
+                creditors: vec!["c".to_string()],
+                shortfall: 400,
+            }
+        );
+        assert_eq!(error.to_string(), "a, b owe $4.00 more than c can be paid");
+    }
+}
@@ -0,0 +1,298 @@
+use std::rc::Rc;
+
+use super::{
+    balance_by_debted_amounts_asc, balance_by_debted_amounts_desc, balance_by_hub,
+    balance_by_min_cash_flow, balance_by_min_fees, balance_by_min_largest_transfer, FeeModel,
+    HubChoice, Transaction,
+};
+use crate::debt::Debt;
+
+/// Name of the strategy used when none has been chosen.
+pub const DEFAULT_STRATEGY: &str = "debted-amounts-asc";
+
+/// Algorithm for settling the debts of a group that sums to zero.
+pub trait BalancingStrategy {
+    /// Name the strategy is registered and remembered by.
+    fn name(&self) -> &'static str;
+
+    /// Short description to show when choosing a strategy.
+    fn label(&self) -> &'static str;
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction>;
+}
+
+/// Pays from the most debted to the most credited, see
+/// [`balance_by_debted_amounts_desc`].
+pub struct DebtedAmountsDesc;
+
+impl BalancingStrategy for DebtedAmountsDesc {
+    fn name(&self) -> &'static str {
+        "debted-amounts-desc"
+    }
+
+    fn label(&self) -> &'static str {
+        "Largest debts to largest credits"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        balance_by_debted_amounts_desc(debts)
+    }
+}
+
+/// Pays from the most debted to the least credited, see
+/// [`balance_by_debted_amounts_asc`].
+pub struct DebtedAmountsAsc;
+
+impl BalancingStrategy for DebtedAmountsAsc {
+    fn name(&self) -> &'static str {
+        DEFAULT_STRATEGY
+    }
+
+    fn label(&self) -> &'static str {
+        "Largest debts to smallest credits"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        balance_by_debted_amounts_asc(debts)
+    }
+}
+
+/// Pays everything through the person with the largest amount, see
+/// [`HubChoice::LeastMoneyMoved`].
+pub struct SpokeHub;
+
+impl BalancingStrategy for SpokeHub {
+    fn name(&self) -> &'static str {
+        "spoke-hub"
+    }
+
+    fn label(&self) -> &'static str {
+        "Everyone pays through one person"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        balance_by_hub(debts, &HubChoice::LeastMoneyMoved)
+    }
+}
+
+/// Pays everything through a nominated treasurer, see
+/// [`HubChoice::Treasurer`], or like [`SpokeHub`] until one is nominated.
+#[derive(Default)]
+pub struct Treasurer {
+    pub name: Option<String>,
+}
+
+impl BalancingStrategy for Treasurer {
+    fn name(&self) -> &'static str {
+        "treasurer"
+    }
+
+    fn label(&self) -> &'static str {
+        "Everyone pays through a treasurer"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        match &self.name {
+            Some(name) => balance_by_hub(debts, &HubChoice::Treasurer(name.clone())),
+            None => balance_by_hub(debts, &HubChoice::LeastMoneyMoved),
+        }
+    }
+}
+
+/// Moves the least money with the fewest transactions, see
+/// [`balance_by_min_cash_flow`].
+pub struct MinCashFlow;
+
+impl BalancingStrategy for MinCashFlow {
+    fn name(&self) -> &'static str {
+        "min-cash-flow"
+    }
+
+    fn label(&self) -> &'static str {
+        "Least money moved"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        balance_by_min_cash_flow(debts)
+    }
+}
+
+/// Keeps the largest single transfer as small as possible, splitting any
+/// transfer over the cap, see [`balance_by_min_largest_transfer`].
+pub struct MinLargestTransfer {
+    pub cap: Option<u32>,
+}
+
+impl BalancingStrategy for MinLargestTransfer {
+    fn name(&self) -> &'static str {
+        "min-largest-transfer"
+    }
+
+    fn label(&self) -> &'static str {
+        "Smallest largest transfer"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        balance_by_min_largest_transfer(debts, self.cap).transactions
+    }
+}
+
+/// Pays the least fees, see [`balance_by_min_fees`]. The transactions are
+/// what each payee receives, which [`price_transactions`] adds the fees to.
+///
+/// [`price_transactions`]: super::price_transactions
+pub struct MinFees {
+    pub fees: FeeModel,
+}
+
+impl BalancingStrategy for MinFees {
+    fn name(&self) -> &'static str {
+        "min-fees"
+    }
+
+    fn label(&self) -> &'static str {
+        "Least fees"
+    }
+
+    fn balance(&self, debts: &[&Debt]) -> Vec<Transaction> {
+        balance_by_min_fees(debts, &self.fees)
+            .into_iter()
+            .map(|priced| priced.transaction)
+            .collect()
+    }
+}
+
+/// Strategies by name, in the order they are offered.
+#[derive(Clone)]
+pub struct StrategyRegistry {
+    strategies: Vec<Rc<dyn BalancingStrategy>>,
+}
+
+impl StrategyRegistry {
+    /// Creates a registry without any strategies.
+    pub fn new() -> Self {
+        Self { strategies: vec![] }
+    }
+
+    /// Adds the strategy, replacing any registered under the same name.
+    pub fn register(&mut self, strategy: Rc<dyn BalancingStrategy>) {
+        match self
+            .strategies
+            .iter()
+            .position(|registered| registered.name() == strategy.name())
+        {
+            Some(i) => self.strategies[i] = strategy,
+            None => self.strategies.push(strategy),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<dyn BalancingStrategy>> {
+        self.strategies
+            .iter()
+            .find(|strategy| strategy.name() == name)
+            .cloned()
+    }
+
+    /// Returns the strategy with the name, or the default one if there is
+    /// none.
+    pub fn get_or_default(&self, name: &str) -> Rc<dyn BalancingStrategy> {
+        self.get(name)
+            .or_else(|| self.get(DEFAULT_STRATEGY))
+            .unwrap_or_else(|| Rc::new(DebtedAmountsAsc))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn BalancingStrategy> {
+        self.strategies.iter().map(|strategy| strategy.as_ref())
+    }
+}
+
+impl Default for StrategyRegistry {
+    /// Creates a registry with the built in strategies.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Rc::new(DebtedAmountsAsc));
+        registry.register(Rc::new(DebtedAmountsDesc));
+        registry.register(Rc::new(SpokeHub));
+        registry.register(Rc::new(Treasurer::default()));
+        registry.register(Rc::new(MinCashFlow));
+        registry.register(Rc::new(MinLargestTransfer { cap: None }));
+        registry.register(Rc::new(MinFees {
+            fees: FeeModel::default(),
+        }));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Nobody;
+
+    impl BalancingStrategy for Nobody {
+        fn name(&self) -> &'static str {
+            "spoke-hub"
+        }
+
+        fn label(&self) -> &'static str {
+            "Nobody pays"
+        }
+
+        fn balance(&self, _debts: &[&Debt]) -> Vec<Transaction> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_strategy_registry() {
+        let mut registry = StrategyRegistry::default();
+        assert_eq!(
+            registry
+                .iter()
+                .map(|strategy| strategy.name())
+                .collect::<Vec<_>>(),
+            [
+                "debted-amounts-asc",
+                "debted-amounts-desc",
+                "spoke-hub",
+                "treasurer",
+                "min-cash-flow",
+                "min-largest-transfer",
+                "min-fees",
+            ]
+        );
+        assert!(registry.get("unknown").is_none());
+        assert_eq!(registry.get_or_default("unknown").name(), DEFAULT_STRATEGY);
+
+        let debts = [
+            Debt {
+                name: "a".to_string(),
+                value: 300,
+            },
+            Debt {
+                name: "b".to_string(),
+                value: -100,
+            },
+            Debt {
+                name: "c".to_string(),
+                value: -200,
+            },
+        ];
+        let partition: Vec<_> = debts.iter().collect();
+        assert_eq!(
+            registry.get("spoke-hub").unwrap().balance(&partition),
+            [
+                Transaction::from("a", "b", 100),
+                Transaction::from("a", "c", 200),
+            ]
+        );
+
+        registry.register(Rc::new(Nobody));
+        assert_eq!(registry.iter().count(), 7);
+        assert!(registry
+            .get("spoke-hub")
+            .unwrap()
+            .balance(&partition)
+            .is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use super::cash_flow::{pay_creditors_directly, MIN_CASH_FLOW_EXACT_LEN};
+use super::largest_transfer::payments;
+use super::Transaction;
+use crate::debt::Debt;
+use crate::partitionings::ZeroSumPartitioningIndices;
+
+/// Largest number of people with outstanding debts for which every way of
+/// paying debtors straight to creditors is searched for one within the
+/// limits.
+pub const TRANSFER_LIMITS_EXACT_LEN: usize = 8;
+
+/// Most transfers each person may send and receive, or `None` for no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransferLimits {
+    pub outgoing: Option<usize>,
+    pub incoming: Option<usize>,
+}
+
+impl TransferLimits {
+    pub fn allows(&self, outgoing: usize, incoming: usize) -> bool {
+        self.outgoing.map_or(true, |limit| outgoing <= limit)
+            && self.incoming.map_or(true, |limit| incoming <= limit)
+    }
+
+    /// Returns the limits raised to at least `limit`, leaving out those that
+    /// aren't set.
+    fn raised_to(&self, limit: usize) -> Self {
+        Self {
+            outgoing: self.outgoing.map(|outgoing| outgoing.max(limit)),
+            incoming: self.incoming.map(|incoming| incoming.max(limit)),
+        }
+    }
+}
+
+/// No settlement stays within the limits.
+///
+/// Paying along a chain needs only one transfer each way, so this only
+/// happens for limits of zero, or for debts that don't balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferLimitError {
+    /// Lowest limits that can be met, raising those that were set together.
+    pub smallest: TransferLimits,
+}
+
+impl fmt::Display for TransferLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let transfers =
+            |limit: usize| format!("{limit} transfer{}", if limit == 1 { "" } else { "s" });
+        let limits: Vec<_> = [
+            self.smallest
+                .outgoing
+                .map(|limit| format!("send {}", transfers(limit))),
+            self.smallest
+                .incoming
+                .map(|limit| format!("receive {}", transfers(limit))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "someone needs to {} to settle", limits.join(" and "))
+    }
+}
+
+impl error::Error for TransferLimitError {}
+
+/// Creates transactions such that nobody sends or receives more transfers
+/// than the limits, or returns the smallest limits that can be met.
+///
+/// Paying debtors straight to creditors moves the least money, so that is
+/// tried first. Paying money around a cycle of payers and payees until one
+/// payment is dropped never adds transfers, so any such settlement within the
+/// limits can be built by repeatedly paying a debtor to a creditor until one
+/// of them is settled. For up to [`TRANSFER_LIMITS_EXACT_LEN`] people each
+/// such order is searched for the fewest transactions, and larger groups pay
+/// the largest debts to the largest credits.
+///
+/// If that exceeds the limits, people pass money on along chains instead,
+/// see [`pay_along_chains`], which needs one transfer each way.
+pub fn balance_with_transfer_limits(
+    debts: &[&Debt],
+    limits: &TransferLimits,
+) -> Result<Vec<Transaction>, TransferLimitError> {
+    let people: Vec<_> = debts
+        .iter()
+        .copied()
+        .filter(|debt| debt.value != 0)
+        .collect();
+    let is_balanced = people.iter().map(|debt| debt.value as i64).sum::<i64>() == 0;
+    if people.len() <= TRANSFER_LIMITS_EXACT_LEN && is_balanced {
+        if let Some(transactions) = search_within(&people, limits) {
+            return Ok(transactions);
+        }
+    } else {
+        let transactions = pay_creditors_directly(debts);
+        let (outgoing, incoming) = most_transfers(&transactions);
+        if limits.allows(outgoing, incoming) {
+            return Ok(transactions);
+        }
+        if !is_balanced {
+            return Err(TransferLimitError {
+                smallest: limits.raised_to(outgoing.max(incoming)),
+            });
+        }
+    }
+
+    if limits.allows(1, 1) {
+        Ok(pay_along_chains(&people))
+    } else {
+        Err(TransferLimitError {
+            smallest: limits.raised_to(1),
+        })
+    }
+}
+
+/// Pays along a chain of each zero sum partition's debtors followed by its
+/// creditors, with everyone passing on what they and those before them owe,
+/// so that nobody sends or receives more than one transfer.
+///
+/// A group that can't be split further needs a transfer for each person but
+/// one, so this gives the fewest transactions, as for
+/// [`balance_by_min_cash_flow`](super::balance_by_min_cash_flow), though it
+/// moves more money than paying creditors directly.
+fn pay_along_chains(people: &[&Debt]) -> Vec<Transaction> {
+    let values: Vec<_> = people.iter().map(|debt| debt.value).collect();
+    let partitioning = if people.len() <= MIN_CASH_FLOW_EXACT_LEN {
+        ZeroSumPartitioningIndices::new(&values)
+            .ok()
+            .and_then(|mut partitionings| partitionings.next())
+    } else {
+        None
+    }
+    .unwrap_or_else(|| vec![(0..people.len()).collect()]);
+    partitioning
+        .into_iter()
+        .flat_map(|partition| {
+            let chain: Vec<_> = partition
+                .iter()
+                .map(|&i| people[i])
+                .filter(|debt| debt.value > 0)
+                .chain(
+                    partition
+                        .iter()
+                        .map(|&i| people[i])
+                        .filter(|debt| debt.value < 0),
+                )
+                .collect();
+            let mut owed = 0;
+            chain
+                .windows(2)
+                .map(|pair| {
+                    owed += pair[0].value as i64;
+                    Transaction::from(&pair[0].name, &pair[1].name, owed as u32)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns the most transfers sent and received by any one person.
+fn most_transfers(transactions: &[Transaction]) -> (usize, usize) {
+    let mut outgoing = HashMap::new();
+    let mut incoming = HashMap::new();
+    for transaction in transactions {
+        *outgoing.entry(&transaction.source).or_insert(0) += 1;
+        *incoming.entry(&transaction.destination).or_insert(0) += 1;
+    }
+    (
+        outgoing.into_values().max().unwrap_or(0),
+        incoming.into_values().max().unwrap_or(0),
+    )
+}
+
+/// Amounts left to settle along with how many transfers each person has
+/// sent and received so far.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    remaining: Vec<i64>,
+    outgoing: Vec<usize>,
+    incoming: Vec<usize>,
+}
+
+impl State {
+    fn pay(&mut self, debtor: usize, creditor: usize, value: i64) {
+        self.remaining[debtor] -= value;
+        self.remaining[creditor] += value;
+        self.outgoing[debtor] += 1;
+        self.incoming[creditor] += 1;
+    }
+
+    fn unpay(&mut self, debtor: usize, creditor: usize, value: i64) {
+        self.remaining[debtor] += value;
+        self.remaining[creditor] -= value;
+        self.outgoing[debtor] -= 1;
+        self.incoming[creditor] -= 1;
+    }
+
+    /// Payments that settle at least one person and stay within the limits.
+    fn payments(&self, limits: &TransferLimits) -> Vec<(usize, usize, i64)> {
+        payments(&self.remaining)
+            .filter(|&(debtor, creditor, _)| {
+                limits.allows(self.outgoing[debtor] + 1, self.incoming[creditor] + 1)
+            })
+            .collect()
+    }
+}
+
+/// Searches every order of settling the people within the limits for the
+/// fewest transactions, returning `None` if there is no such order.
+fn search_within(people: &[&Debt], limits: &TransferLimits) -> Option<Vec<Transaction>> {
+    let mut state = State {
+        remaining: people.iter().map(|debt| debt.value as i64).collect(),
+        outgoing: vec![0; people.len()],
+        incoming: vec![0; people.len()],
+    };
+    let mut memo = HashMap::new();
+    fewest(&mut state, limits, &mut memo)?;
+
+    // Follow the payments that lead to the fewest transactions.
+    let mut transactions = vec![];
+    while let Some(len) = memo.get(&state).copied().flatten() {
+        if len == 0 {
+            break;
+        }
+        let (debtor, creditor, value) = state
+            .payments(limits)
+            .into_iter()
+            .find(|&(debtor, creditor, value)| {
+                state.pay(debtor, creditor, value);
+                let next = memo.get(&state).copied().flatten();
+                state.unpay(debtor, creditor, value);
+                next == Some(len - 1)
+            })
+            .unwrap();
+        state.pay(debtor, creditor, value);
+        transactions.push(Transaction::from(
+            &people[debtor].name,
+            &people[creditor].name,
+            value as u32,
+        ));
+    }
+    Some(transactions)
+}
+
+/// Fewest transactions that settle the remaining amounts within the limits,
+/// or `None` if they can't be settled.
+fn fewest(
+    state: &mut State,
+    limits: &TransferLimits,
+    memo: &mut HashMap<State, Option<usize>>,
+) -> Option<usize> {
+    if let Some(&len) = memo.get(state) {
+        return len;
+    }
+    let len = if state.remaining.iter().all(|&value| value == 0) {
+        Some(0)
+    } else {
+        let mut fewest_len = None;
+        for (debtor, creditor, value) in state.payments(limits) {
+            state.pay(debtor, creditor, value);
+            let next = fewest(state, limits, memo);
+            state.unpay(debtor, creditor, value);
+            if let Some(next) = next {
+                fewest_len = Some(fewest_len.map_or(next + 1, |len: usize| len.min(next + 1)));
+            }
+        }
+        fewest_len
+    };
+    memo.insert(state.clone(), len);
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+
+    #[test]
+    fn test_balance_with_transfer_limits() {
+        let debts = debts(&[400, 300, 200, -500, -400]);
+        let partition: Vec<_> = debts.iter().collect();
+        let limits = TransferLimits {
+            outgoing: Some(1),
+            incoming: None,
+        };
+        let transactions = balance_with_transfer_limits(&partition, &limits).unwrap();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(most_transfers(&transactions), (1, 2));
+        // Paying the largest debts first has b send twice.
+        assert_eq!(most_transfers(&pay_creditors_directly(&partition)), (2, 2));
+    }
+
+    #[test]
+    fn test_balance_with_transfer_limits_chain() {
+        let debts = debts(&[800, -200, -200, -200, -200, 100, -100]);
+        let partition: Vec<_> = debts.iter().collect();
+        // Paying creditors directly has a send to all four of b, c, d and e,
+        // so the money is passed on along a chain instead.
+        let transactions = balance_with_transfer_limits(
+            &partition,
+            &TransferLimits {
+                outgoing: Some(1),
+                incoming: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            transactions,
+            [
+                Transaction::from("a", "b", 800),
+                Transaction::from("b", "c", 600),
+                Transaction::from("c", "d", 400),
+                Transaction::from("d", "e", 200),
+                Transaction::from("f", "g", 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_balance_with_transfer_limits_too_low() {
+        let debts = debts(&[800, -200, -200, -200, -200]);
+        let partition: Vec<_> = debts.iter().collect();
+        let error = balance_with_transfer_limits(
+            &partition,
+            &TransferLimits {
+                outgoing: Some(0),
+                incoming: Some(2),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.smallest,
+            TransferLimits {
+                outgoing: Some(1),
+                incoming: Some(2),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "someone needs to send 1 transfer and receive 2 transfers to settle"
+        );
+    }
+}
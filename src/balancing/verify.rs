@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use super::Transaction;
+use crate::debt::Debt;
+
+/// Why transactions don't settle a set of debts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettlementError {
+    /// The transaction at `index` names someone without a debt.
+    UnknownName { index: usize, name: String },
+    /// The transaction at `index` doesn't transfer a positive amount, which
+    /// for unsigned values means it transfers nothing.
+    NotPositive { index: usize },
+    /// `name` ends up `excess` past settling their debt. A positive excess is
+    /// paid to them and a negative one paid by them.
+    Overpayment { name: String, excess: i64 },
+}
+
+impl fmt::Display for SettlementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownName { index, name } => {
+                write!(f, "transaction {index} is with {name}, who has no debt")
+            }
+            Self::NotPositive { index } => write!(f, "transaction {index} transfers nothing"),
+            Self::Overpayment { name, excess } => write!(
+                f,
+                "{name} {} ${}.{:02} more than they {}",
+                if *excess < 0 { "pays" } else { "is paid" },
+                excess.unsigned_abs() / 100,
+                excess.unsigned_abs() % 100,
+                if *excess < 0 { "owe" } else { "are owed" },
+            ),
+        }
+    }
+}
+
+impl error::Error for SettlementError {}
+
+/// Checks that the transactions only settle the debts, returning what each
+/// person still owes afterwards, or is owed if negative. These are zero if
+/// the debts are settled.
+///
+/// Debts with the same name are taken to be the same person, in the order
+/// they first appear. People may pass money on, as long as nobody pays or is
+/// paid more than their debt.
+pub fn verify_settlement(
+    debts: &[&Debt],
+    transactions: &[Transaction],
+) -> Result<Vec<Debt>, SettlementError> {
+    let mut names = vec![];
+    let mut indices = HashMap::new();
+    let mut values: Vec<i64> = vec![];
+    for debt in debts {
+        let i = *indices.entry(debt.name.as_str()).or_insert_with(|| {
+            names.push(debt.name.as_str());
+            values.push(0);
+            names.len() - 1
+        });
+        values[i] += debt.value as i64;
+    }
+
+    let mut residuals = values.clone();
+    for (index, transaction) in transactions.iter().enumerate() {
+        if transaction.value == 0 {
+            return Err(SettlementError::NotPositive { index });
+        }
+        let [source, destination] = [&transaction.source, &transaction.destination].map(|name| {
+            indices
+                .get(name.as_str())
+                .copied()
+                .ok_or_else(|| SettlementError::UnknownName {
+                    index,
+                    name: name.clone(),
+                })
+        });
+        residuals[source?] -= transaction.value as i64;
+        residuals[destination?] += transaction.value as i64;
+    }
+
+    for (i, &residual) in residuals.iter().enumerate() {
+        let excess = if residual < values[i].min(0) {
+            residual - values[i].min(0)
+        } else if residual > values[i].max(0) {
+            residual - values[i].max(0)
+        } else {
+            0
+        };
+        if excess != 0 {
+            return Err(SettlementError::Overpayment {
+                name: names[i].to_string(),
+                excess,
+            });
+        }
+    }
+    Ok(names
+        .into_iter()
+        .zip(residuals)
+        .map(|(name, residual)| Debt {
+            name: name.to_string(),
+            // Between zero and the debt, so it fits.
+            value: residual as i32,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+    use crate::balancing::{
+        balance_by_hub, balance_by_min_largest_transfer, balance_by_restricted_edges,
+        balance_by_spoke_hub, balance_with_transfer_limits, BalancingStrategy, Fee, FeeModel,
+        HubChoice, MinFees, PaymentEdges, StrategyRegistry, TransferLimits, Treasurer,
+    };
+
+    #[test]
+    fn test_verify_settlement() {
+        let debts = debts(&[300, 0, -100, -200]);
+        let partition: Vec<_> = debts.iter().collect();
+        let residuals = |values: &[i32]| {
+            values
+                .iter()
+                .zip(&debts)
+                .map(|(&value, debt)| Debt {
+                    name: debt.name.clone(),
+                    value,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            verify_settlement(
+                &partition,
+                &[
+                    Transaction::from("a", "b", 300),
+                    Transaction::from("b", "c", 100),
+                    Transaction::from("b", "d", 200),
+                ]
+            ),
+            Ok(residuals(&[0, 0, 0, 0]))
+        );
+        assert_eq!(
+            verify_settlement(&partition, &[Transaction::from("a", "d", 150)]),
+            Ok(residuals(&[150, 0, -100, -50]))
+        );
+        assert_eq!(
+            verify_settlement(&partition, &[Transaction::from("a", "z", 100)]),
+            Err(SettlementError::UnknownName {
+                index: 0,
+                name: "z".to_string(),
+            })
+        );
+        assert_eq!(
+            verify_settlement(&partition, &[Transaction::from("a", "c", 0)]),
+            Err(SettlementError::NotPositive { index: 0 })
+        );
+        let error = verify_settlement(
+            &partition,
+            &[
+                Transaction::from("a", "c", 100),
+                Transaction::from("a", "d", 250),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            SettlementError::Overpayment {
+                name: "a".to_string(),
+                excess: -50,
+            }
+        );
+        assert_eq!(error.to_string(), "a pays $0.50 more than they owe");
+        assert_eq!(
+            verify_settlement(&partition, &[Transaction::from("a", "d", 300)]),
+            Err(SettlementError::Overpayment {
+                name: "d".to_string(),
+                excess: 100,
+            })
+        );
+    }
+
+    #[test]
+    fn test_every_strategy_settles() {
+        let groups: [&[i32]; 4] = [
+            &[4000, 2000, 1090, 1000, -1080, -1340, -2410, -3260],
+            &[500, 300, -400, -300, -100],
+            &[300, 0, -100, -200],
+            &[-700, 250, 250, 0, 200],
+        ];
+        let fees = FeeModel {
+            global: Fee {
+                fixed: 30,
+                basis_points: 290,
+            },
+            ..Default::default()
+        };
+        for values in groups {
+            let debts = debts(values);
+            let partition: Vec<_> = debts.iter().collect();
+            // Every registered strategy, then those that need settings.
+            let mut settlements: Vec<(String, Vec<Transaction>)> = StrategyRegistry::default()
+                .iter()
+                .map(|strategy| (strategy.name().to_string(), strategy.balance(&partition)))
+                .collect();
+            settlements.push((
+                "min-fees".to_string(),
+                MinFees { fees: fees.clone() }.balance(&partition),
+            ));
+            settlements.push((
+                "treasurer".to_string(),
+                Treasurer {
+                    name: Some("c".to_string()),
+                }
+                .balance(&partition),
+            ));
+            for choice in [HubChoice::LargestBalance, HubChoice::LeastMoneyMoved] {
+                settlements.push((format!("{choice:?}"), balance_by_hub(&partition, &choice)));
+            }
+            for hub_index in 0..debts.len() {
+                settlements.push((
+                    format!("spoke-hub {hub_index}"),
+                    balance_by_spoke_hub(&partition, hub_index).unwrap(),
+                ));
+            }
+            settlements.push((
+                "min-largest-transfer capped".to_string(),
+                balance_by_min_largest_transfer(&partition, Some(250)).transactions,
+            ));
+            settlements.push((
+                "restricted".to_string(),
+                balance_by_restricted_edges(&partition, &PaymentEdges::default()).unwrap(),
+            ));
+            settlements.push((
+                "transfer limits".to_string(),
+                balance_with_transfer_limits(&partition, &TransferLimits::default()).unwrap(),
+            ));
+
+            for (name, transactions) in settlements {
+                let residuals = verify_settlement(&partition, &transactions);
+                assert!(
+                    matches!(&residuals, Ok(residuals) if residuals.iter().all(|debt| debt.value == 0)),
+                    "{name} {values:?}: {residuals:?}",
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+use payback::settlement_worker::SettlementWorker;
+use yew_agent::PrivateWorker;
+
+fn main() {
+    SettlementWorker::register();
+}
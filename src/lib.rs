@@ -0,0 +1,7 @@
+pub mod balancing;
+pub mod debt;
+pub mod partitionings;
+pub mod ranking;
+pub mod settlement_engine;
+pub mod settlement_worker;
+pub mod transaction_pages;
@@ -1,22 +1,68 @@
-#![feature(drain_filter)]
-#![feature(slice_group_by)]
-
-pub mod balancing;
 mod components;
-pub mod debt;
-pub mod partitionings;
 mod state;
 
 use console_log;
+use gloo_storage::{SessionStorage, Storage};
 use log::Level;
-use std::rc::Rc;
-use web_sys::window;
+use web_sys::{window, HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
+use yew_agent::use_bridge;
 
-use balancing::balance_by_debted_amounts_asc;
 use components::entries::Entries;
-use partitionings::longest_zero_sum_partitionings;
-use state::State;
+use payback::balancing::{
+    price_transactions, verify_settlement, Fee, FeeModel, StrategyRegistry, DEFAULT_STRATEGY,
+};
+use payback::ranking::Objective;
+use payback::settlement_worker::{
+    SettlementInput, SettlementOptions, SettlementOutput, SettlementWorker,
+};
+use payback::transaction_pages::TransactionPage;
+use state::{Entry, State};
+
+/// Session storage key of the name of the chosen balancing strategy.
+const STRATEGY_KEY: &str = "payback.strategy";
+
+/// Session storage key of the name of the nominated treasurer.
+const TREASURER_KEY: &str = "payback.treasurer";
+
+/// Name of the strategy that pays through a nominated treasurer.
+const TREASURER_STRATEGY: &str = "treasurer";
+
+/// Session storage key of the fees of transfers, for everyone and for chosen
+/// payers and payees.
+const FEES_KEY: &str = "payback.fees";
+
+/// Name of the strategy that pays the least fees, which shows what each payer
+/// sends including them.
+const MIN_FEES_STRATEGY: &str = "min-fees";
+
+/// Session storage key of the most sent in one payment.
+const CAP_KEY: &str = "payback.cap";
+
+/// Name of the strategy that keeps the largest transfer small, splitting any
+/// over the cap.
+const MIN_LARGEST_TRANSFER_STRATEGY: &str = "min-largest-transfer";
+
+/// Parses a non-negative number to a whole number of hundredths of it, such as
+/// cents of a dollar amount or basis points of a percentage.
+fn parse_hundredths(number: &str) -> Option<u32> {
+    let parsed = number.trim().parse::<f64>().ok()?;
+    (parsed >= 0.0 && parsed * 100.0 <= u32::MAX as f64).then(|| (parsed * 100.0).round() as u32)
+}
+
+/// Formats a whole number of hundredths, such as cents, with two decimals.
+fn format_hundredths(hundredths: u32) -> String {
+    format!("{}.{:02}", hundredths / 100, hundredths % 100)
+}
+
+/// Formats a fee as its fixed dollar amount plus its percentage.
+fn format_fee(fee: &Fee) -> String {
+    format!(
+        "${} plus {}%",
+        format_hundredths(fee.fixed),
+        format_hundredths(fee.basis_points)
+    )
+}
 
 fn main() {
     console_log::init_with_level(Level::Debug).expect("error initialising logger");
@@ -26,7 +72,20 @@ fn main() {
 #[function_component(App)]
 fn app() -> Html {
     let state = use_reducer(State::new);
-    let transaction_partitioning_index = use_state(|| 0);
+    let generation = use_mut_ref(|| 0_u64);
+    let page = use_state(TransactionPage::default);
+    let progress = use_state(|| None::<(u64, u64)>);
+    let objective = use_state(Objective::default);
+    let strategies = use_memo(|_| StrategyRegistry::default(), ());
+    let strategy = use_state(|| {
+        SessionStorage::get::<String>(STRATEGY_KEY).unwrap_or_else(|_| DEFAULT_STRATEGY.to_string())
+    });
+    let treasurer = use_state(|| SessionStorage::get::<String>(TREASURER_KEY).ok());
+    let fees = use_state(|| SessionStorage::get::<FeeModel>(FEES_KEY).unwrap_or_default());
+    // Whose fees are shown and changed, everyone's if there is no payer.
+    let fee_payer = use_state(|| None::<String>);
+    let fee_payee = use_state(|| None::<String>);
+    let cap = use_state(|| SessionStorage::get::<u32>(CAP_KEY).ok());
     let show_help_text = use_state(|| false);
 
     let on_toggle_help_text = {
@@ -34,74 +93,342 @@ fn app() -> Html {
         move |_| show_help_text.set(!*show_help_text)
     };
 
-    let transaction_partitionings = use_memo(
-        |entries| {
-            let debts: Vec<_> = entries
-                .clone()
-                .into_iter()
-                .map(|entry| entry.debt)
-                .collect();
-            longest_zero_sum_partitionings(&debts)
-                .into_iter()
-                .map(|partitioning| {
-                    partitioning
-                        .into_iter()
-                        .flat_map(|partition| balance_by_debted_amounts_asc(&partition))
-                        .collect::<Vec<_>>()
-                })
-                .collect::<Vec<_>>()
-        },
-        state.entries.clone(),
-    );
+    let settlement_worker = {
+        let generation = generation.clone();
+        let page = page.clone();
+        let progress = progress.clone();
+        use_bridge::<SettlementWorker, _>(move |output| match output {
+            SettlementOutput::Progress {
+                generation: output_generation,
+                steps,
+                total_steps,
+            } if output_generation == *generation.borrow() => {
+                progress.set(Some((steps, total_steps)));
+            }
+            SettlementOutput::Page {
+                generation: output_generation,
+                page: output_page,
+            } if output_generation == *generation.borrow() => {
+                progress.set(None);
+                page.set(output_page);
+            }
+            // Output for entries that have since changed.
+            _ => {}
+        })
+    };
 
-    let transactions = transaction_partitionings
-        .get(*transaction_partitioning_index)
-        .unwrap_or(&vec![])
-        .clone();
+    // Only the treasurer is remembered across reloads, not the entries, so a
+    // treasurer that isn't among the entries, such as before they are entered
+    // again, is kept but not used.
+    let is_entry =
+        |entries: &[Entry], name: &str| entries.iter().any(|entry| entry.debt.name == name);
 
     {
-        let transaction_partitioning_index = transaction_partitioning_index.clone();
-        let transaction_partitionings = Rc::clone(&transaction_partitionings);
+        let generation = generation.clone();
+        let page = page.clone();
+        let settlement_worker = settlement_worker.clone();
         use_effect_with_deps(
-            move |_| transaction_partitioning_index.set(0),
-            transaction_partitionings,
+            move |(entries, objective, strategy, treasurer, fees, cap)| {
+                *generation.borrow_mut() += 1;
+                page.set(Default::default());
+                settlement_worker.send(SettlementInput::Settle {
+                    generation: *generation.borrow(),
+                    debts: entries.iter().map(|entry| entry.debt.clone()).collect(),
+                    options: Box::new(SettlementOptions {
+                        objective: Some(*objective),
+                        strategy: strategy.clone(),
+                        treasurer: treasurer.clone().filter(|name| is_entry(entries, name)),
+                        fees: fees.clone(),
+                        cap: *cap,
+                        ..Default::default()
+                    }),
+                });
+            },
+            (
+                state.entries.clone(),
+                *objective,
+                (*strategy).clone(),
+                (*treasurer).clone(),
+                (*fees).clone(),
+                *cap,
+            ),
         );
     }
 
+    // Pages only arrive for the current entries, whose amounts with the
+    // page's rounding adjustments should be settled exactly.
+    {
+        let entries = state.entries.clone();
+        use_effect_with_deps(
+            move |page| {
+                if !page.transactions.is_empty() {
+                    let mut debts: Vec<_> =
+                        entries.iter().map(|entry| entry.debt.clone()).collect();
+                    for adjustment in &page.adjustments {
+                        if let Some(debt) =
+                            debts.iter_mut().find(|debt| debt.name == adjustment.name)
+                        {
+                            debt.value += adjustment.value;
+                        }
+                    }
+                    let debts: Vec<_> = debts.iter().collect();
+                    let residuals = verify_settlement(&debts, &page.transactions);
+                    debug_assert!(
+                        matches!(&residuals, Ok(residuals) if residuals.iter().all(|debt| debt.value == 0)),
+                        "transactions don't settle the entries: {residuals:?}",
+                    );
+                }
+            },
+            (*page).clone(),
+        );
+    }
+
+    let transactions = page.transactions.clone();
+    let entries = state.entries.clone();
+    let chosen_treasurer = treasurer.as_deref().filter(|name| is_entry(&entries, name));
+    // The transactions are what each payee receives, so with fees the payers
+    // are shown what to send on top.
+    let lines: Vec<String> = if *strategy == MIN_FEES_STRATEGY && *fees != FeeModel::default() {
+        price_transactions(&transactions, &fees)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        transactions.iter().map(ToString::to_string).collect()
+    };
+
+    let on_change_objective = {
+        let objective = objective.clone();
+        move |e: Event| {
+            let index = e
+                .target_unchecked_into::<HtmlSelectElement>()
+                .selected_index();
+            if let Some(&selected) = usize::try_from(index)
+                .ok()
+                .and_then(|index| Objective::ALL.get(index))
+            {
+                objective.set(selected);
+            }
+        }
+    };
+
+    let on_change_strategy = {
+        let strategies = strategies.clone();
+        let strategy = strategy.clone();
+        move |e: Event| {
+            let index = e
+                .target_unchecked_into::<HtmlSelectElement>()
+                .selected_index();
+            if let Some(selected) = usize::try_from(index)
+                .ok()
+                .and_then(|index| strategies.iter().nth(index))
+            {
+                // Failing to remember the choice only loses it on reload.
+                SessionStorage::set(STRATEGY_KEY, selected.name()).ok();
+                strategy.set(selected.name().to_string());
+            }
+        }
+    };
+
+    let on_change_treasurer = {
+        let entries = entries.clone();
+        let treasurer = treasurer.clone();
+        move |e: Event| {
+            let index = e
+                .target_unchecked_into::<HtmlSelectElement>()
+                .selected_index();
+            // The first option chooses the hub automatically.
+            let selected = usize::try_from(index - 1)
+                .ok()
+                .and_then(|index| entries.get(index))
+                .map(|entry| entry.debt.name.clone());
+            match &selected {
+                // Failing to remember the choice only loses it on reload.
+                Some(name) => {
+                    SessionStorage::set(TREASURER_KEY, name).ok();
+                }
+                None => SessionStorage::delete(TREASURER_KEY),
+            }
+            treasurer.set(selected);
+        }
+    };
+
+    // Fees are set for everyone, for a payer, or for a payer paying a payee.
+    // Each shows the fee that applies to it until it is set.
+    let fee_payer_names: Vec<String> = entries
+        .iter()
+        .map(|entry| entry.debt.name.clone())
+        .collect();
+    let chosen_fee_payer = fee_payer.as_deref().filter(|name| is_entry(&entries, name));
+    let fee_payee_names: Vec<String> = fee_payer_names
+        .iter()
+        .filter(|name| Some(name.as_str()) != chosen_fee_payer)
+        .cloned()
+        .collect();
+    let chosen_fee_payee = fee_payee
+        .as_deref()
+        .filter(|name| chosen_fee_payer.is_some() && fee_payee_names.iter().any(|n| n == name));
+    let fee_scope = (
+        chosen_fee_payer.map(str::to_string),
+        chosen_fee_payee.map(str::to_string),
+    );
+    let shown_fee = match &fee_scope {
+        (Some(payer), Some(payee)) => fees.fee(payer, payee),
+        (Some(payer), None) => fees.people.get(payer).copied().unwrap_or(fees.global),
+        _ => fees.global,
+    };
+    let has_fee_override = match &fee_scope {
+        (Some(payer), Some(payee)) => fees.pairs.contains_key(&(payer.clone(), payee.clone())),
+        (Some(payer), None) => fees.people.contains_key(payer),
+        _ => false,
+    };
+    let mut fee_overrides: Vec<String> =
+        fees.people
+            .iter()
+            .map(|(payer, fee)| format!("From {payer}: {}", format_fee(fee)))
+            .chain(fees.pairs.iter().map(|((payer, payee), fee)| {
+                format!("From {payer} to {payee}: {}", format_fee(fee))
+            }))
+            .collect();
+    fee_overrides.sort();
+
+    let on_change_fee_payer = {
+        let fee_payer_names = fee_payer_names.clone();
+        let fee_payer = fee_payer.clone();
+        move |e: Event| {
+            let index = e
+                .target_unchecked_into::<HtmlSelectElement>()
+                .selected_index();
+            // The first option sets everyone's fee.
+            fee_payer.set(
+                usize::try_from(index - 1)
+                    .ok()
+                    .and_then(|index| fee_payer_names.get(index))
+                    .cloned(),
+            );
+        }
+    };
+
+    let on_change_fee_payee = {
+        let fee_payee_names = fee_payee_names.clone();
+        let fee_payee = fee_payee.clone();
+        move |e: Event| {
+            let index = e
+                .target_unchecked_into::<HtmlSelectElement>()
+                .selected_index();
+            // The first option sets the payer's fee to anyone.
+            fee_payee.set(
+                usize::try_from(index - 1)
+                    .ok()
+                    .and_then(|index| fee_payee_names.get(index))
+                    .cloned(),
+            );
+        }
+    };
+
+    let set_fee = {
+        let fees = fees.clone();
+        move |fee: Option<Fee>| {
+            let mut changed = (*fees).clone();
+            match (fee_scope.clone(), fee) {
+                ((Some(payer), Some(payee)), Some(fee)) => {
+                    changed.pairs.insert((payer, payee), fee);
+                }
+                ((Some(payer), Some(payee)), None) => {
+                    changed.pairs.remove(&(payer, payee));
+                }
+                ((Some(payer), None), Some(fee)) => {
+                    changed.people.insert(payer, fee);
+                }
+                ((Some(payer), None), None) => {
+                    changed.people.remove(&payer);
+                }
+                (_, fee) => changed.global = fee.unwrap_or_default(),
+            }
+            // Failing to remember the fees only loses them on reload.
+            SessionStorage::set(FEES_KEY, &changed).ok();
+            fees.set(changed);
+        }
+    };
+
+    let on_change_fee = |update: fn(&mut Fee, u32)| {
+        let set_fee = set_fee.clone();
+        move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            if let Some(hundredths) = parse_hundredths(&input.value()) {
+                let mut changed = shown_fee;
+                update(&mut changed, hundredths);
+                input.set_value(&format_hundredths(hundredths));
+                set_fee(Some(changed));
+            }
+        }
+    };
+    let on_change_fixed_fee = on_change_fee(|fee, cents| fee.fixed = cents);
+    // Fees of 100% or more could never be paid.
+    let on_change_percentage_fee =
+        on_change_fee(|fee, basis_points| fee.basis_points = basis_points.min(9999));
+    // Clearing a payer's or pair's fee falls back to the one that covers it.
+    let on_clear_fee = move |_| set_fee(None);
+
+    let on_change_cap = {
+        let cap = cap.clone();
+        move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            // Anything but a positive amount leaves payments uncapped.
+            let changed = parse_hundredths(&input.value()).filter(|&cents| cents > 0);
+            match changed {
+                // Failing to remember the cap only loses it on reload.
+                Some(cents) => {
+                    input.set_value(&format_hundredths(cents));
+                    SessionStorage::set(CAP_KEY, cents).ok();
+                }
+                None => {
+                    input.set_value("");
+                    SessionStorage::delete(CAP_KEY);
+                }
+            }
+            cap.set(changed);
+        }
+    };
+
     let on_copy_transactions = {
-        let transactions = transactions.clone();
+        let lines = lines.clone();
         window()
             .and_then(|window| window.navigator().clipboard())
             .map(|clipboard| {
                 move |_| {
-                    if !transactions.is_empty() {
-                        clipboard.write_text(
-                            &(transactions
-                                .iter()
-                                .map(|transaction| transaction.to_string())
-                                .collect::<Vec<_>>()
-                                .join("\n")),
-                        );
+                    if !lines.is_empty() {
+                        clipboard.write_text(&lines.join("\n"));
                     }
                 }
             })
     };
 
     let on_decrement_transaction_partitioning_index = {
-        let transaction_partitioning_index = transaction_partitioning_index.clone();
+        let generation = generation.clone();
+        let settlement_worker = settlement_worker.clone();
+        let index = page.index;
         move |_| {
-            if *transaction_partitioning_index > 0 {
-                transaction_partitioning_index.set(*transaction_partitioning_index - 1);
+            if index > 0 {
+                settlement_worker.send(SettlementInput::Page {
+                    generation: *generation.borrow(),
+                    index: index - 1,
+                });
             }
         }
     };
 
     let on_increment_transaction_partitioning_index = {
-        let transaction_partitioning_index = transaction_partitioning_index.clone();
-        let partitionings_len = transaction_partitionings.len();
+        let generation = generation.clone();
+        let settlement_worker = settlement_worker.clone();
+        let index = page.index;
+        let has_next_page = ((index + 1) as u64) < page.len;
         move |_| {
-            if *transaction_partitioning_index < partitionings_len - 1 {
-                transaction_partitioning_index.set(*transaction_partitioning_index + 1);
+            if has_next_page {
+                settlement_worker.send(SettlementInput::Page {
+                    generation: *generation.borrow(),
+                    index: index + 1,
+                });
             }
         }
     };
@@ -131,15 +458,40 @@ fn app() -> Html {
                             </p>
                             <p>
                                 {
-                                    "Once all the debts sum to zero, the \
-                                    transactions required to settle everyone's \
-                                    debts will be displayed below."
+                                    "Once all the debts sum to zero, or to \
+                                    within a few cents of it, the transactions \
+                                    required to settle everyone's debts will \
+                                    be displayed below."
                                 }
                             </p>
                         </div>
                     }
                 }}
                 <Entries {state} />
+                if let Some(imbalance) = &page.imbalance {
+                    <div class="imbalance">
+                        <div>{imbalance.to_string()}</div>
+                        if imbalance.names.iter().any(|name| !name.is_empty()) {
+                            <div class="search-note">
+                                {format!(
+                                    "Check the amounts of {}",
+                                    imbalance
+                                        .names
+                                        .iter()
+                                        .filter(|name| !name.is_empty())
+                                        .cloned()
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                )}
+                            </div>
+                        }
+                    </div>
+                }
+                if let Some((steps, total_steps)) = *progress {
+                    <div class="search-note">
+                        {format!("Searching… {}%", steps * 100 / total_steps.max(1))}
+                    </div>
+                }
                 {html! {
                     if !transactions.is_empty() {
                         <div class="output-actions">
@@ -154,7 +506,25 @@ fn app() -> Html {
                                 },
                                 None => html! { <div></div> }
                             }}
-                            if transaction_partitionings.len() > 1 {
+                            if page.len > 1 {
+                                <select
+                                    class="output-actions--objective"
+                                    onchange={on_change_objective}
+                                >
+                                    {Objective::ALL
+                                        .iter()
+                                        .map(|option| {
+                                            html! {
+                                                <option selected={*option == *objective}>
+                                                    {option.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect::<Html>()
+                                    }
+                                </select>
+                            }
+                            if page.len > 1 {
                                 <div class="output-actions--pagination">
                                     <button
                                         onclick={on_decrement_transaction_partitioning_index}
@@ -164,8 +534,8 @@ fn app() -> Html {
                                     <div>
                                         {format!(
                                             "{}/{}",
-                                            *transaction_partitioning_index + 1,
-                                            transaction_partitionings.len()
+                                            page.index + 1,
+                                            page.len
                                         )}
                                     </div>
                                     <button
@@ -176,13 +546,185 @@ fn app() -> Html {
                                 </div>
                             }
                         </div>
+                        <div class="strategy">
+                            <label for="strategy">{"Pay by"}</label>
+                            <select id="strategy" onchange={on_change_strategy}>
+                                {strategies
+                                    .iter()
+                                    .map(|option| {
+                                        html! {
+                                            <option selected={option.name() == *strategy}>
+                                                {option.label()}
+                                            </option>
+                                        }
+                                    })
+                                    .collect::<Html>()
+                                }
+                            </select>
+                            if *strategy == TREASURER_STRATEGY {
+                                <select id="treasurer" onchange={on_change_treasurer}>
+                                    <option selected={chosen_treasurer.is_none()}>
+                                        {"Largest amount"}
+                                    </option>
+                                    {entries
+                                        .iter()
+                                        .map(|entry| {
+                                            html! {
+                                                <option
+                                                    selected={
+                                                        chosen_treasurer
+                                                            == Some(entry.debt.name.as_str())
+                                                    }
+                                                >
+                                                    {entry.debt.name.clone()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect::<Html>()
+                                    }
+                                </select>
+                            }
+                            if *strategy == MIN_FEES_STRATEGY {
+                                <select id="fee-payer" onchange={on_change_fee_payer}>
+                                    <option selected={chosen_fee_payer.is_none()}>
+                                        {"Everyone"}
+                                    </option>
+                                    {fee_payer_names
+                                        .iter()
+                                        .map(|name| {
+                                            html! {
+                                                <option
+                                                    selected={chosen_fee_payer == Some(name.as_str())}
+                                                >
+                                                    {name.clone()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect::<Html>()
+                                    }
+                                </select>
+                                if chosen_fee_payer.is_some() {
+                                    <label for="fee-payee">{"to"}</label>
+                                    <select id="fee-payee" onchange={on_change_fee_payee}>
+                                        <option selected={chosen_fee_payee.is_none()}>
+                                            {"Anyone"}
+                                        </option>
+                                        {fee_payee_names
+                                            .iter()
+                                            .map(|name| {
+                                                html! {
+                                                    <option
+                                                        selected={
+                                                            chosen_fee_payee == Some(name.as_str())
+                                                        }
+                                                    >
+                                                        {name.clone()}
+                                                    </option>
+                                                }
+                                            })
+                                            .collect::<Html>()
+                                        }
+                                    </select>
+                                }
+                                <label for="fixed-fee">{"pays $"}</label>
+                                <input
+                                    id="fixed-fee"
+                                    class="strategy--fee"
+                                    value={format_hundredths(shown_fee.fixed)}
+                                    inputmode="decimal"
+                                    onchange={on_change_fixed_fee}
+                                />
+                                <label for="percentage-fee">{"plus"}</label>
+                                <input
+                                    id="percentage-fee"
+                                    class="strategy--fee"
+                                    value={format_hundredths(shown_fee.basis_points)}
+                                    inputmode="decimal"
+                                    onchange={on_change_percentage_fee}
+                                />
+                                <span>{"%"}</span>
+                                if has_fee_override {
+                                    <button onclick={on_clear_fee}>{"Clear"}</button>
+                                }
+                            }
+                            if *strategy == MIN_LARGEST_TRANSFER_STRATEGY {
+                                <label for="cap">{"Cap $"}</label>
+                                <input
+                                    id="cap"
+                                    class="strategy--cap"
+                                    value={cap.map(format_hundredths).unwrap_or_default()}
+                                    placeholder="none"
+                                    inputmode="decimal"
+                                    onchange={on_change_cap}
+                                />
+                            }
+                        </div>
+                        if *strategy == MIN_FEES_STRATEGY {
+                            {fee_overrides
+                                .iter()
+                                .map(|fee_override| {
+                                    html! {
+                                        <div class="search-note">{fee_override}</div>
+                                    }
+                                })
+                                .collect::<Html>()
+                            }
+                        }
+                        if *strategy == MIN_LARGEST_TRANSFER_STRATEGY && page.extra_len > 0 {
+                            <div class="search-note">
+                                {format!(
+                                    "Splitting at the cap takes {} more transfer{}",
+                                    page.extra_len,
+                                    if page.extra_len == 1 { "" } else { "s" },
+                                )}
+                            </div>
+                        }
+                        {page
+                            .interchangeable
+                            .iter()
+                            .map(|names| {
+                                html! {
+                                    <div class="search-note">
+                                        {format!("{} are interchangeable", names.join(", "))}
+                                    </div>
+                                }
+                            })
+                            .collect::<Html>()
+                        }
+                        {page
+                            .adjustments
+                            .iter()
+                            .map(|adjustment| {
+                                html! {
+                                    <div class="search-note">
+                                        {format!("Rounding: {}", adjustment)}
+                                    </div>
+                                }
+                            })
+                            .collect::<Html>()
+                        }
+                        if !page.proven_optimal {
+                            <div class="search-note">
+                                {"Best found, not proven optimal"}
+                            </div>
+                        }
+                        if page.ranked_len > 0 && page.ranked_len < page.len {
+                            <div class="search-note">
+                                {format!(
+                                    "Only the first {} of {} are ordered by {}",
+                                    page.ranked_len,
+                                    page.len,
+                                    objective.label().to_lowercase(),
+                                )}
+                            </div>
+                        }
                         <div class="transactions">
-                            {transactions
+                            {lines
                                 .iter()
-                                .map(|transaction| {
+                                .map(|line| {
                                     html! {
                                         <div class="transaction">
-                                            {transaction}
+                                            {line}
                                         </div>
                                     }
                                 })
@@ -1,92 +1,234 @@
-use std::iter::Sum;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::Hash;
 
-/// Given a collection of summable items, returns a collection of partitionings
+mod amount;
+mod constraints;
+mod exact_cover;
+mod exhaustive;
+mod heuristic;
+mod meet_in_middle;
+mod search;
+mod subset_dp;
+mod symmetry;
+mod tolerance;
+
+use amount::checked_sum;
+pub use amount::Amount;
+pub use constraints::{
+    constrained_zero_sum_partitioning_indices, constrained_zero_sum_partitionings,
+    ConstrainedPartitionings, Constraint,
+};
+use exact_cover::longest_exact_covers;
+pub use exhaustive::{ExhaustivePartitionings, MAX_EXHAUSTIVE_LEN};
+pub use heuristic::{
+    heuristic_zero_sum_partitioning, heuristic_zero_sum_partitioning_indices,
+    HeuristicPartitioning, HeuristicPartitioningIndices,
+};
+pub use meet_in_middle::{minimal_zero_sum_subsets, MinimalZeroSumSubsets};
+pub use search::{
+    search_longest_zero_sum_partitioning, CancellationToken, SearchConfig, SearchOutcome,
+};
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub use subset_dp::longest_zero_sum_partitionings_par;
+pub use subset_dp::{
+    count_longest_zero_sum_partitionings, longest_zero_sum_partitionings_dp,
+    zero_sum_partitionings_iter, PartitioningSearch, ZeroSumPartitioningIndices,
+    ZeroSumPartitionings, MAX_DP_LEN,
+};
+pub use symmetry::{distinct_zero_sum_partitionings_iter, DistinctPartitionings, Symmetry};
+pub use tolerance::{
+    leftover_adjustments, partitioning_adjustments, tolerant_zero_sum_partitionings, Leftover,
+};
+
+/// Largest number of non-zero items that the subset enumeration will attempt.
+///
+/// Every subset of the non-zero items is visited, so the work doubles with
+/// each item beyond this.
+pub const MAX_SEARCH_LEN: usize = 32;
+
+/// Largest number of ways of pairing up items of opposite amounts that are
+/// returned without a search. Each of `k` items of an amount can be paired
+/// with any of the `k` of its opposite, giving `k!` ways.
+pub const MAX_PAIRINGS_LEN: u64 = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitioningError {
+    /// There are more non-zero items than the solver can handle.
+    TooManyItems { len: usize, max: usize },
+    /// Summing some of the items overflowed their amount type.
+    Overflow,
+    /// A constraint names an item beyond the `len` items of the set.
+    UnknownItem { constraint: Constraint, len: usize },
+    /// No partitioning satisfies all of these constraints together, though
+    /// it would without any one of them. `amounts` are those of the two items
+    /// of each constraint, if the amount type implements [`Amount::to_i128`].
+    Infeasible {
+        constraints: Vec<Constraint>,
+        amounts: Vec<Option<[i128; 2]>>,
+    },
+    /// The items don't sum to zero, so they can't be split into zero sum
+    /// partitions. `net` is their sum and `entries` are the indices of the
+    /// items on the side that is over, any of which could be lowered to
+    /// balance them. Both are only known if the amount type implements
+    /// [`Amount::to_i128`], and otherwise `net` is `None` and `entries` empty.
+    Unbalanced {
+        net: Option<i128>,
+        entries: Vec<usize>,
+    },
+}
+
+impl fmt::Display for PartitioningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyItems { len, max } => write!(
+                f,
+                "{len} people have outstanding debts but at most {max} can be partitioned",
+            ),
+            Self::Overflow => write!(f, "debts are too large to add up"),
+            Self::UnknownItem { constraint, len } => {
+                write!(f, "can't {constraint} as there are only {len} items")
+            }
+            Self::Infeasible {
+                constraints,
+                amounts,
+            } => write!(
+                f,
+                "constraints can't all be met: {}",
+                constraints
+                    .iter()
+                    .zip(amounts)
+                    .map(|(constraint, amounts)| match amounts {
+                        Some(amounts) => constraint.describe(amounts),
+                        None => constraint.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Self::Unbalanced { net: Some(net), .. } => {
+                write!(f, "amounts sum to {net} rather than zero")
+            }
+            Self::Unbalanced { net: None, .. } => write!(f, "amounts don't sum to zero"),
+        }
+    }
+}
+
+impl error::Error for PartitioningError {}
+
+/// Returns [`PartitioningError::Unbalanced`] if the set doesn't sum to zero.
+pub fn check_balanced<T>(set: &[T]) -> Result<(), PartitioningError>
+where
+    T: Amount,
+{
+    let sum = checked_sum(set)?;
+    if sum.is_zero() {
+        return Ok(());
+    }
+    let net = sum.to_i128();
+    let entries = match net {
+        Some(net) => (0..set.len())
+            .filter(|&index| {
+                set[index]
+                    .to_i128()
+                    .map_or(false, |value| value.signum() == net.signum())
+            })
+            .collect(),
+        None => vec![],
+    };
+    Err(PartitioningError::Unbalanced { net, entries })
+}
+
+/// Given a collection of amounts, returns a collection of partitionings
 /// with the maximum number of partitions such that each partition sums to zero.
 ///
 /// A partition being a subset of items from the given set.
 ///
 /// A partitioning being a collection of disjoint partitions such that their
 /// union is the given set.
-pub fn longest_zero_sum_partitionings<'a, T>(set: &'a [T]) -> Vec<Vec<Vec<&'a T>>>
+///
+/// Items that sum to zero by themselves always form their own partition, so
+/// only the remaining items count towards [`MAX_SEARCH_LEN`]. Neither do items
+/// that can all be paired with one of the opposite amount, as then every
+/// partition of a longest partitioning is such a pair, as long as repeated
+/// amounts don't pair up in more than [`MAX_PAIRINGS_LEN`] ways.
+///
+/// Returns [`PartitioningError::Unbalanced`] if the set doesn't sum to zero.
+pub fn longest_zero_sum_partitionings<T>(set: &[T]) -> Result<Vec<Vec<Vec<&T>>>, PartitioningError>
 where
-    i32: Sum<&'a T>,
+    T: Amount + Eq + Hash,
 {
     if set.is_empty() {
-        return vec![vec![]];
+        return Ok(vec![vec![]]);
     }
 
-    // Collection of bit strings with each bit corresponding to an item in the
-    // given set. 1 means the item is in the set and 0 means it isn't.
-    let mut subset_bit_strings: Vec<_> = (1..2_u64.pow(set.len() as u32)).collect();
-    subset_bit_strings.sort_by_key(|item| item.count_zeros());
-    let mut zero_sum_subset_bit_strings = vec![];
-    while let Some(bit_string) = subset_bit_strings.pop() {
-        let subset_sum: i32 = set
-            .iter()
-            .enumerate()
-            .filter(|(index, _)| (bit_string >> index) & 1 == 1)
-            .map(|(_, item)| item)
-            .sum();
-        if subset_sum == 0 {
-            subset_bit_strings
-                .drain_filter(|subset_bit_string| *subset_bit_string & bit_string == bit_string);
-            zero_sum_subset_bit_strings.push(bit_string);
+    check_balanced(set)?;
+    let (zero_indices, indices): (Vec<_>, Vec<_>) =
+        (0..set.len()).partition(|&index| set[index].is_zero());
+    let values: Vec<_> = indices.iter().map(|&index| set[index].clone()).collect();
+    // Zero sum subsets of the non-zero items, as positions in `indices`,
+    // that don't contain a smaller zero sum subset. If the items pair up, the
+    // most partitions is half the items so only the pairs are needed.
+    let rows: Vec<_> = match opposite_pairs(&values) {
+        Some(pairs) => pairs,
+        None if values.len() > MAX_SEARCH_LEN => {
+            return Err(PartitioningError::TooManyItems {
+                len: values.len(),
+                max: MAX_SEARCH_LEN,
+            });
         }
-    }
+        None => minimal_zero_sum_subsets(&values)?.collect(),
+    };
 
-    if zero_sum_subset_bit_strings.is_empty() {
-        return vec![];
+    // Every partition of a partitioning with the most partitions is one of
+    // these subsets, otherwise it could be split further, so the partitionings
+    // are exactly the covers of the non-zero items by the most of them. Each
+    // has at least two items as a single non-zero item can't sum to zero.
+    let partitionings = longest_exact_covers(indices.len(), &rows, 2)
+        .into_iter()
+        .map(|cover| {
+            cover
+                .into_iter()
+                .map(|row| rows[row].iter().map(|&i| &set[indices[i]]).collect())
+                .chain(zero_indices.iter().map(|&index| vec![&set[index]]))
+                .collect()
+        })
+        .collect();
+    Ok(partitionings)
+}
+
+/// Returns every pair of positions of opposite amounts, or `None` unless each
+/// amount appears as often as its opposite and they can be paired up in at
+/// most [`MAX_PAIRINGS_LEN`] ways.
+fn opposite_pairs<T>(values: &[T]) -> Option<Vec<Vec<usize>>>
+where
+    T: Amount + Eq + Hash,
+{
+    let mut positions: HashMap<&T, Vec<usize>> = HashMap::new();
+    for (i, value) in values.iter().enumerate() {
+        positions.entry(value).or_default().push(i);
     }
-    let set_bit_string = 2_u64.pow(set.len() as u32) - 1;
-    // Collection of bit strings with each bit corresponding to an item in
-    // `zero_sum_subset_bit_strings`. 1 means the item is in the set and 0 means
-    // it isn't.
-    let mut subset_set_bit_strings: Vec<_> =
-        (1..2_u64.pow(zero_sum_subset_bit_strings.len() as u32)).collect();
-    subset_set_bit_strings.sort_by_key(|item| item.count_zeros());
-    subset_set_bit_strings
-        .group_by(|a, b| a.count_zeros() == b.count_zeros())
-        .find_map(|group| {
-            let partitionings: Vec<_> = group
-                .iter()
-                .filter_map(|&subset_set_bit_string| {
-                    let subset_bit_strings: Vec<_> = zero_sum_subset_bit_strings
-                        .iter()
-                        .enumerate()
-                        .filter(|(index, _)| (subset_set_bit_string >> *index) & 1 == 1)
-                        .map(|(_, &bit_string)| bit_string)
-                        .collect();
-                    if subset_bit_strings.iter().sum::<u64>() == set_bit_string
-                        && subset_bit_strings
-                            .iter()
-                            .fold(0, |acc, bit_string| acc | bit_string)
-                            == set_bit_string
-                    {
-                        Some(subset_bit_strings)
-                    } else {
-                        None
-                    }
-                })
-                .map(|subset_bit_strings| {
-                    subset_bit_strings
-                        .into_iter()
-                        .map(|subset_bit_string| {
-                            set.iter()
-                                .enumerate()
-                                .filter(|(index, _)| (subset_bit_string >> index) & 1 == 1)
-                                .map(|(_, partition)| partition)
-                                .collect::<Vec<_>>()
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .collect();
-            if partitionings.is_empty() {
-                None
-            } else {
-                Some(partitionings)
+    let mut pairings_len: u64 = 1;
+    for (value, value_positions) in &positions {
+        let opposite_positions = positions.get(&value.checked_neg()?)?;
+        if opposite_positions.len() != value_positions.len() {
+            return None;
+        }
+        // Count each amount and its opposite once.
+        if value_positions[0] < opposite_positions[0] {
+            for k in 2..=value_positions.len() as u64 {
+                pairings_len = pairings_len
+                    .checked_mul(k)
+                    .filter(|&len| len <= MAX_PAIRINGS_LEN)?;
             }
-        })
-        .unwrap_or(vec![])
+        }
+    }
+    let mut pairs = vec![];
+    for (i, value) in values.iter().enumerate() {
+        let opposites = &positions[&value.checked_neg()?];
+        pairs.extend(opposites.iter().filter(|&&j| i < j).map(|&j| vec![i, j]));
+    }
+    Some(pairs)
 }
 
 #[cfg(test)]
@@ -99,16 +241,116 @@ mod tests {
         let set: Vec<_> = (0..length)
             .flat_map(|i| vec![2_i32.pow(i), -2_i32.pow(i)])
             .collect();
-        let partitionings = longest_zero_sum_partitionings(&set);
+        let partitionings = longest_zero_sum_partitionings(&set).unwrap();
         assert_eq!(partitionings.len(), 1);
         assert_eq!(partitionings[0].len(), length as usize);
     }
 
     #[test]
     fn test_longest_zero_sum_partitionings_with_multiple_results() {
-        let partitionings = longest_zero_sum_partitionings(&[-6, -2, -1, 2, 3, 4]);
+        let partitionings = longest_zero_sum_partitionings(&[-6, -2, -1, 2, 3, 4]).unwrap();
         assert_eq!(partitionings.len(), 2);
         assert_eq!(partitionings[0].len(), 2);
         assert_eq!(partitionings[1].len(), 2);
     }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_more_than_63_items() {
+        let mut set = vec![0; 80];
+        set.extend([-6, -2, -1, 2, 3, 4]);
+        let partitionings = longest_zero_sum_partitionings(&set).unwrap();
+        assert_eq!(partitionings.len(), 2);
+        assert_eq!(partitionings[0].len(), 82);
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_more_than_63_non_zero_items() {
+        // 40 people owing different amounts to 40 people owed them, and two
+        // pairs of people owing and owed the same amount.
+        let mut set: Vec<_> = (1..=40).flat_map(|i| [i * 100, -i * 100]).collect();
+        set.extend([5, 5, -5, -5]);
+        let partitionings = longest_zero_sum_partitionings(&set).unwrap();
+        // Each 5 can be paired with either -5.
+        assert_eq!(partitionings.len(), 2);
+        for partitioning in &partitionings {
+            assert_eq!(partitioning.len(), 42);
+            assert!(partitioning
+                .iter()
+                .all(|partition| partition.len() == 2 && *partition[0] == -*partition[1]));
+        }
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_repeated_pairs() {
+        let mut set: Vec<_> = (1..=30).flat_map(|i| [i * 100, -i * 100]).collect();
+        set.extend([25; 6]);
+        set.extend([-25; 6]);
+        // The six 25s can be paired with the six -25s in 6! ways.
+        assert_eq!(longest_zero_sum_partitionings(&set).unwrap().len(), 720);
+
+        // 40! ways are too many to return.
+        let mut set = vec![25; 40];
+        set.extend([-25; 40]);
+        assert_eq!(
+            longest_zero_sum_partitionings(&set),
+            Err(PartitioningError::TooManyItems {
+                len: 80,
+                max: MAX_SEARCH_LEN,
+            })
+        );
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_overflow() {
+        assert_eq!(
+            longest_zero_sum_partitionings(&[i32::MAX, i32::MAX, -1]),
+            Err(PartitioningError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_unbalanced() {
+        assert_eq!(
+            longest_zero_sum_partitionings(&[300, -500, 120, 500]),
+            Err(PartitioningError::Unbalanced {
+                net: Some(420),
+                entries: vec![0, 2, 3],
+            })
+        );
+        assert_eq!(
+            PartitioningError::Unbalanced {
+                net: Some(-5),
+                entries: vec![1],
+            }
+            .to_string(),
+            "amounts sum to -5 rather than zero"
+        );
+        assert_eq!(
+            PartitioningError::Unbalanced {
+                net: None,
+                entries: vec![],
+            }
+            .to_string(),
+            "amounts don't sum to zero"
+        );
+        // Balance is checked before the number of items.
+        let set: Vec<_> = (1..=(MAX_SEARCH_LEN as i32 + 1)).collect();
+        assert!(matches!(
+            longest_zero_sum_partitionings(&set),
+            Err(PartitioningError::Unbalanced { .. })
+        ));
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_too_many_items() {
+        let mut set: Vec<_> = (1..=MAX_SEARCH_LEN as i32).collect();
+        set.push(-set.iter().sum::<i32>());
+        assert_eq!(
+            longest_zero_sum_partitionings(&set),
+            Err(PartitioningError::TooManyItems {
+                len: MAX_SEARCH_LEN + 1,
+                max: MAX_SEARCH_LEN,
+            })
+        );
+    }
 }
@@ -0,0 +1,80 @@
+use super::PartitioningError;
+
+/// Number that items of a set can be partitioned by, such as an integer
+/// amount of cents, a rational number or a money type.
+///
+/// Arithmetic is checked so that overflow is reported instead of wrapping.
+pub trait Amount: Clone {
+    fn zero() -> Self;
+
+    /// Adds `other`, returning `None` if the result overflows.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    /// Negates the amount, returning `None` if the result overflows.
+    fn checked_neg(&self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool;
+
+    /// Amount as a whole number of its smallest unit, for reporting it, or
+    /// `None` if it has no such form.
+    fn to_i128(&self) -> Option<i128> {
+        None
+    }
+}
+
+macro_rules! impl_amount_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl Amount for $int {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$int>::checked_add(*self, *other)
+                }
+
+                fn checked_neg(&self) -> Option<Self> {
+                    <$int>::checked_neg(*self)
+                }
+
+                fn is_zero(&self) -> bool {
+                    *self == 0
+                }
+
+                fn to_i128(&self) -> Option<i128> {
+                    Some(*self as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_amount_for_int!(i8, i16, i32, i64, i128, isize);
+
+/// Sums the amounts, failing if any partial sum overflows.
+pub(super) fn checked_sum<'a, T, I>(amounts: I) -> Result<T, PartitioningError>
+where
+    T: Amount + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    amounts.into_iter().try_fold(T::zero(), |sum, amount| {
+        sum.checked_add(amount).ok_or(PartitioningError::Overflow)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_sum() {
+        assert_eq!(checked_sum(&[1_i64, -2, 3]), Ok(2));
+        assert_eq!(checked_sum::<i32, _>(&[]), Ok(0));
+        assert_eq!(
+            checked_sum(&[i32::MAX, 1]),
+            Err(PartitioningError::Overflow)
+        );
+        assert_eq!(Amount::checked_neg(&i8::MIN), None);
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+
+use super::amount::checked_sum;
+use super::exhaustive::{subset_sums, ExhaustivePartitionings};
+use super::{Amount, PartitioningError};
+
+/// Requirement on which items may share a partition, by their indices in the
+/// set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// The items must be in the same partition, such as a couple settling as
+    /// a household.
+    Together(usize, usize),
+    /// The items must be in different partitions.
+    Apart(usize, usize),
+}
+
+impl Constraint {
+    /// Indices of the two items the constraint is on.
+    pub fn items(&self) -> [usize; 2] {
+        match *self {
+            Self::Together(a, b) | Self::Apart(a, b) => [a, b],
+        }
+    }
+
+    /// Describes the constraint by the amounts of its items, keeping their
+    /// indices to tell equal amounts apart.
+    pub(super) fn describe(&self, amounts: &[i128; 2]) -> String {
+        let [a, b] = self.items();
+        let [a_amount, b_amount] = amounts;
+        let placement = match self {
+            Self::Together(..) => "together",
+            Self::Apart(..) => "apart",
+        };
+        format!("keep {a_amount} (item {a}) and {b_amount} (item {b}) {placement}")
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Together(a, b) => write!(f, "keep item {a} and item {b} together"),
+            Self::Apart(a, b) => write!(f, "keep item {a} and item {b} apart"),
+        }
+    }
+}
+
+/// Same as [`super::longest_zero_sum_partitionings`] but only returns
+/// partitionings that satisfy the constraints, which may have fewer
+/// partitions than the unconstrained ones.
+///
+/// Returns [`PartitioningError::Infeasible`] if no partitioning satisfies all
+/// the constraints, naming conflicting ones that would all be needed for the
+/// conflict, and [`PartitioningError::UnknownItem`] if a constraint names an
+/// item that isn't in the set.
+pub fn constrained_zero_sum_partitionings<'a, T>(
+    set: &'a [T],
+    constraints: &[Constraint],
+) -> Result<Vec<Vec<Vec<&'a T>>>, PartitioningError>
+where
+    T: Amount,
+{
+    Ok(constrained_zero_sum_partitioning_indices(set, constraints)?
+        .map(|partitioning| {
+            partitioning
+                .into_iter()
+                .map(|partition| partition.into_iter().map(|i| &set[i]).collect())
+                .collect()
+        })
+        .collect())
+}
+
+/// Same as [`constrained_zero_sum_partitionings`] but returns an iterator
+/// yielding indices into the set.
+pub fn constrained_zero_sum_partitioning_indices<T>(
+    set: &[T],
+    constraints: &[Constraint],
+) -> Result<ConstrainedPartitionings, PartitioningError>
+where
+    T: Amount,
+{
+    if let Some(&constraint) = constraints
+        .iter()
+        .find(|constraint| constraint.items().iter().any(|&i| i >= set.len()))
+    {
+        return Err(PartitioningError::UnknownItem {
+            constraint,
+            len: set.len(),
+        });
+    }
+    if let Some(partitionings) = solve(set, constraints)? {
+        return Ok(partitionings);
+    }
+    // Drop each constraint that isn't needed for the conflict, leaving a set
+    // where every constraint is.
+    let mut conflicting = constraints.to_vec();
+    let mut i = 0;
+    while i < conflicting.len() {
+        let mut without = conflicting.clone();
+        without.remove(i);
+        if solve(set, &without)?.is_none() {
+            conflicting = without;
+        } else {
+            i += 1;
+        }
+    }
+    let amounts = conflicting
+        .iter()
+        .map(|constraint| {
+            let [a, b] = constraint.items();
+            Some([set[a].to_i128()?, set[b].to_i128()?])
+        })
+        .collect();
+    Err(PartitioningError::Infeasible {
+        constraints: conflicting,
+        amounts,
+    })
+}
+
+/// Iterator over the partitionings with the most zero sum partitions that
+/// satisfy the constraints, yielding indices into the set.
+pub struct ConstrainedPartitionings {
+    /// Items kept together, with each group treated as a single item.
+    groups: Vec<Vec<usize>>,
+    partitionings: Option<ExhaustivePartitionings>,
+}
+
+impl ConstrainedPartitionings {
+    /// Number of partitionings yielded over the whole iteration, including
+    /// any that have already been yielded.
+    pub fn total_len(&self) -> u64 {
+        self.partitionings
+            .as_ref()
+            .map_or(0, ExhaustivePartitionings::total_len)
+    }
+}
+
+impl Iterator for ConstrainedPartitionings {
+    type Item = Vec<Vec<usize>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let partitioning = self.partitionings.as_mut()?.next()?;
+        Some(
+            partitioning
+                .into_iter()
+                .map(|partition| {
+                    let mut items: Vec<_> = partition
+                        .into_iter()
+                        .flat_map(|group| self.groups[group].iter().copied())
+                        .collect();
+                    items.sort();
+                    items
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Returns the representative of the merged items containing `i`, shortening
+/// the path to it along the way.
+fn root(roots: &mut [usize], mut i: usize) -> usize {
+    while roots[i] != i {
+        roots[i] = roots[roots[i]];
+        i = roots[i];
+    }
+    i
+}
+
+/// Searches for partitionings satisfying the constraints, returning `None` if
+/// there are none even though the set sums to zero.
+fn solve<T>(
+    set: &[T],
+    constraints: &[Constraint],
+) -> Result<Option<ConstrainedPartitionings>, PartitioningError>
+where
+    T: Amount,
+{
+    // Merge items that are kept together, directly or through others.
+    let mut roots: Vec<_> = (0..set.len()).collect();
+    for constraint in constraints {
+        if let &Constraint::Together(a, b) = constraint {
+            let (a, b) = (root(&mut roots, a), root(&mut roots, b));
+            roots[a.max(b)] = a.min(b);
+        }
+    }
+    let mut group_of = vec![0; set.len()];
+    let mut groups: Vec<Vec<usize>> = vec![];
+    for i in 0..set.len() {
+        let r = root(&mut roots, i);
+        if r == i {
+            group_of[i] = groups.len();
+            groups.push(vec![i]);
+        } else {
+            group_of[i] = group_of[r];
+            groups[group_of[r]].push(i);
+        }
+    }
+
+    let values = groups
+        .iter()
+        .map(|group| checked_sum(group.iter().map(|&i| &set[i])))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (zero_groups, groups_to_search): (Vec<_>, Vec<_>) =
+        (0..groups.len()).partition(|&group| values[group].is_zero());
+    if !checked_sum(&values)?.is_zero() {
+        return Ok(Some(ConstrainedPartitionings {
+            groups,
+            partitionings: None,
+        }));
+    }
+
+    // Bit masks over the searched groups of the groups each one must be kept
+    // apart from. Zero groups are partitions of their own so are always apart.
+    let mut position = vec![None; groups.len()];
+    for (bit, &group) in groups_to_search.iter().enumerate() {
+        position[group] = Some(bit);
+    }
+    let mut apart = vec![0_usize; groups_to_search.len()];
+    for constraint in constraints {
+        if let &Constraint::Apart(a, b) = constraint {
+            let (a, b) = (group_of[a], group_of[b]);
+            if a == b {
+                return Ok(None);
+            }
+            if let (Some(a), Some(b)) = (position[a], position[b]) {
+                apart[a] |= 1 << b;
+                apart[b] |= 1 << a;
+            }
+        }
+    }
+
+    let search_values: Vec<_> = groups_to_search
+        .iter()
+        .map(|&group| values[group].clone())
+        .collect();
+    let sums = subset_sums(&search_values)?;
+    let mut allowed = vec![true; sums.len()];
+    for mask in 1..sums.len() {
+        let lowest = mask.trailing_zeros() as usize;
+        allowed[mask] = allowed[mask & (mask - 1)] && apart[lowest] & mask == 0;
+    }
+    let balanced = sums
+        .iter()
+        .zip(allowed)
+        .map(|(sum, allowed)| allowed && sum.is_zero())
+        .collect();
+    let partitionings = ExhaustivePartitionings::new(groups_to_search, zero_groups, balanced)?;
+    if partitionings.total_len() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(ConstrainedPartitionings {
+        groups,
+        partitionings: Some(partitionings),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constrained_zero_sum_partitionings_together() {
+        let set = [-6, -2, -1, 2, 3, 4];
+        let partitionings =
+            constrained_zero_sum_partitionings(&set, &[Constraint::Together(1, 3)]).unwrap();
+        assert_eq!(partitionings, [vec![vec![&-6, &-1, &3, &4], vec![&-2, &2]]]);
+        let partitionings =
+            constrained_zero_sum_partitionings(&set, &[Constraint::Apart(0, 3)]).unwrap();
+        assert_eq!(partitionings, [vec![vec![&-6, &-1, &3, &4], vec![&-2, &2]]]);
+    }
+
+    #[test]
+    fn test_constrained_zero_sum_partitionings_fewer_partitions() {
+        let set = [1, -1, 2, -2, 3, -3, 0];
+        let partitionings =
+            constrained_zero_sum_partitionings(&set, &[Constraint::Apart(0, 1)]).unwrap();
+        assert_eq!(
+            partitionings,
+            [vec![vec![&1, &2, &-3], vec![&-1, &-2, &3], vec![&0]]]
+        );
+    }
+
+    #[test]
+    fn test_constrained_zero_sum_partitionings_unbalanced() {
+        let set = [1, -2];
+        assert_eq!(
+            constrained_zero_sum_partitionings(&set, &[Constraint::Apart(0, 1)]),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn test_constrained_zero_sum_partitionings_infeasible() {
+        // Keeping -1 with -2 leaves 1 and 2 to pay them together, so the
+        // first constraint isn't part of the conflict.
+        let set = [1, -1, 2, -2];
+        let constraints = [
+            Constraint::Together(0, 2),
+            Constraint::Together(1, 3),
+            Constraint::Apart(2, 1),
+        ];
+        assert_eq!(
+            constrained_zero_sum_partitionings(&set, &constraints),
+            Err(PartitioningError::Infeasible {
+                constraints: vec![Constraint::Together(1, 3), Constraint::Apart(2, 1)],
+                amounts: vec![Some([-1, -2]), Some([2, -1])],
+            })
+        );
+
+        let set = [1, -1, 1, -1];
+        let constraints = [
+            Constraint::Together(0, 1),
+            Constraint::Together(1, 2),
+            Constraint::Apart(0, 2),
+        ];
+        let error = constrained_zero_sum_partitionings(&set, &constraints).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "constraints can't all be met: keep 1 (item 0) and -1 (item 1) together, \
+            keep -1 (item 1) and 1 (item 2) together, keep 1 (item 0) and 1 (item 2) apart"
+        );
+    }
+
+    #[test]
+    fn test_constrained_zero_sum_partitionings_unknown_item() {
+        let set = [1, -1];
+        let error = constrained_zero_sum_partitionings(
+            &set,
+            &[Constraint::Together(0, 1), Constraint::Apart(1, 2)],
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            PartitioningError::UnknownItem {
+                constraint: Constraint::Apart(1, 2),
+                len: 2,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "can't keep item 1 and item 2 apart as there are only 2 items"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+/// Returns every exact cover of the columns `0..columns` with the most rows,
+/// as indices into `rows`.
+///
+/// A cover is a selection of rows such that each column is in exactly one of
+/// them. Covers are found with Knuth's Algorithm X on dancing links, always
+/// branching on the column in the fewest remaining rows. Choosing a row
+/// removes every row that overlaps it, so overlapping combinations are never
+/// extended. Branches that can't reach the most rows found so far, given
+/// that every row has at least `min_row_len` columns, are cut short.
+pub(super) fn longest_exact_covers(
+    columns: usize,
+    rows: &[Vec<usize>],
+    min_row_len: usize,
+) -> Vec<Vec<usize>> {
+    let mut links = DancingLinks::new(columns, rows);
+    let mut search = Search {
+        min_row_len: min_row_len.max(1),
+        remaining_columns: columns,
+        chosen: vec![],
+        covers: vec![],
+    };
+    search.run(&mut links);
+    search.covers
+}
+
+/// Circular doubly linked lists of the ones in a sparse matrix, in both
+/// directions, with a header node per column and a root header at 0.
+struct DancingLinks {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Column header of each node.
+    column: Vec<usize>,
+    /// Row index of each node, unused for headers.
+    row: Vec<usize>,
+    /// Number of remaining rows in each column, indexed by header.
+    size: Vec<usize>,
+}
+
+impl DancingLinks {
+    fn new(columns: usize, rows: &[Vec<usize>]) -> Self {
+        let headers = columns + 1;
+        let mut links = Self {
+            left: (0..headers).map(|i| (i + headers - 1) % headers).collect(),
+            right: (0..headers).map(|i| (i + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![0; headers],
+            size: vec![0; headers],
+        };
+        for (r, row) in rows.iter().enumerate() {
+            let first = links.column.len();
+            for (k, &c) in row.iter().enumerate() {
+                let header = c + 1;
+                let node = links.column.len();
+                links.column.push(header);
+                links.row.push(r);
+                // Append to the bottom of the column.
+                links.up.push(links.up[header]);
+                links.down.push(header);
+                let above = links.up[header];
+                links.down[above] = node;
+                links.up[header] = node;
+                links.size[header] += 1;
+                // Append to the end of the row.
+                if k == 0 {
+                    links.left.push(node);
+                    links.right.push(node);
+                } else {
+                    links.left.push(links.left[first]);
+                    links.right.push(first);
+                    let last = links.left[first];
+                    links.right[last] = node;
+                    links.left[first] = node;
+                }
+            }
+        }
+        links
+    }
+
+    /// Removes the column from the header list and every row in it from the
+    /// other columns.
+    fn cover(&mut self, header: usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    /// Reverses [`Self::cover`], relinking in the opposite order.
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+
+    /// Remaining column with the fewest rows, or `None` if every column has
+    /// been covered.
+    fn smallest_column(&self) -> Option<usize> {
+        let mut best = None;
+        let mut header = self.right[0];
+        while header != 0 {
+            if best.map_or(true, |best| self.size[header] < self.size[best]) {
+                best = Some(header);
+            }
+            header = self.right[header];
+        }
+        best
+    }
+}
+
+struct Search {
+    min_row_len: usize,
+    remaining_columns: usize,
+    chosen: Vec<usize>,
+    covers: Vec<Vec<usize>>,
+}
+
+impl Search {
+    fn run(&mut self, links: &mut DancingLinks) {
+        let header = match links.smallest_column() {
+            Some(header) => header,
+            None => {
+                let len = self.chosen.len();
+                match self.covers.first().map(Vec::len) {
+                    Some(best) if best > len => {}
+                    Some(best) if best == len => self.covers.push(self.chosen.clone()),
+                    _ => self.covers = vec![self.chosen.clone()],
+                }
+                return;
+            }
+        };
+        if let Some(best) = self.covers.first().map(Vec::len) {
+            if self.chosen.len() + self.remaining_columns / self.min_row_len < best {
+                return;
+            }
+        }
+        links.cover(header);
+        let mut i = links.down[header];
+        while i != header {
+            self.chosen.push(links.row[i]);
+            let mut row_len = 1;
+            let mut j = links.right[i];
+            while j != i {
+                links.cover(links.column[j]);
+                row_len += 1;
+                j = links.right[j];
+            }
+            self.remaining_columns -= row_len;
+            self.run(links);
+            self.remaining_columns += row_len;
+            let mut j = links.left[i];
+            while j != i {
+                links.uncover(links.column[j]);
+                j = links.left[j];
+            }
+            self.chosen.pop();
+            i = links.down[i];
+        }
+        links.uncover(header);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_exact_covers() {
+        // Knuth's example, which has a single exact cover.
+        let rows = [
+            vec![2, 4, 5],
+            vec![0, 3, 6],
+            vec![1, 2, 5],
+            vec![0, 3],
+            vec![1, 6],
+            vec![3, 4, 6],
+        ];
+        assert_eq!(longest_exact_covers(7, &rows, 1), [vec![3, 0, 4]]);
+
+        let rows = [vec![0, 1], vec![2, 3], vec![0, 1, 2, 3], vec![1, 2]];
+        assert_eq!(longest_exact_covers(4, &rows, 2), [vec![0, 1]]);
+        // Column 4 is in no row so can't be covered.
+        assert!(longest_exact_covers(5, &rows, 2).is_empty());
+        assert_eq!(longest_exact_covers(0, &[], 2), [Vec::<usize>::new()]);
+    }
+}
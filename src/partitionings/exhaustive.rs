@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::iter;
+
+use super::{Amount, PartitioningError};
+
+/// Largest number of non-zero items that the exhaustive search will attempt.
+///
+/// Unlike the exact search, every split of every subset is visited, so the
+/// work triples with each item beyond this.
+pub const MAX_EXHAUSTIVE_LEN: usize = 16;
+
+/// Marks subsets that can't be partitioned into balanced subsets.
+const UNBALANCED: u8 = u8::MAX;
+
+/// Returns the sum of every subset of the values, indexed by bit mask.
+pub(super) fn subset_sums<T: Amount>(values: &[T]) -> Result<Vec<T>, PartitioningError> {
+    if values.len() > MAX_EXHAUSTIVE_LEN {
+        return Err(PartitioningError::TooManyItems {
+            len: values.len(),
+            max: MAX_EXHAUSTIVE_LEN,
+        });
+    }
+    let len = 1 << values.len();
+    let mut sums = Vec::with_capacity(len);
+    sums.push(T::zero());
+    for mask in 1..len {
+        let lowest = mask.trailing_zeros() as usize;
+        let sum = sums[mask & (mask - 1)]
+            .checked_add(&values[lowest])
+            .ok_or(PartitioningError::Overflow)?;
+        sums.push(sum);
+    }
+    Ok(sums)
+}
+
+/// Iterator over the partitionings with the most partitions where each
+/// partition is one of the subsets marked as balanced, yielding indices into
+/// the set.
+///
+/// For each subset of the non-zero items, the table holds the most partitions
+/// it can be split into. Since balanced partitions don't necessarily combine
+/// into balanced subsets, such as when they only sum to within a tolerance of
+/// zero, each subset tries every partition containing its lowest item rather
+/// than relying on zero sum prefixes.
+pub struct ExhaustivePartitionings {
+    indices: Vec<usize>,
+    zero_indices: Vec<usize>,
+    /// Whether each subset of the non-zero items, by bit mask, can form a
+    /// partition.
+    balanced: Vec<bool>,
+    most: Vec<u8>,
+    stack: Vec<Frame>,
+    total_len: u64,
+}
+
+/// Partitions that can be split off a remaining mask in the depth first
+/// search, and the next one to try.
+struct Frame {
+    mask: usize,
+    partitions: Vec<usize>,
+    next: usize,
+}
+
+impl ExhaustivePartitionings {
+    /// Builds the table for the items at `indices`, where `balanced` is
+    /// indexed by bit masks over `indices`. The items at `zero_indices` each
+    /// form their own partition.
+    pub(super) fn new(
+        indices: Vec<usize>,
+        zero_indices: Vec<usize>,
+        balanced: Vec<bool>,
+    ) -> Result<Self, PartitioningError> {
+        if indices.len() > MAX_EXHAUSTIVE_LEN {
+            return Err(PartitioningError::TooManyItems {
+                len: indices.len(),
+                max: MAX_EXHAUSTIVE_LEN,
+            });
+        }
+        let len = 1 << indices.len();
+        let mut partitionings = Self {
+            indices,
+            zero_indices,
+            balanced,
+            most: vec![UNBALANCED; len],
+            stack: vec![],
+            total_len: 0,
+        };
+        partitionings.most[0] = 0;
+        for mask in 1..len {
+            partitionings.most[mask] = partitionings
+                .candidates(mask)
+                .filter(|&partition| partitionings.most[mask & !partition] != UNBALANCED)
+                .map(|partition| partitionings.most[mask & !partition] + 1)
+                .max()
+                .unwrap_or(UNBALANCED);
+        }
+        let full_mask = len - 1;
+        if partitionings.most[full_mask] != UNBALANCED {
+            partitionings.total_len = partitionings.count_from(full_mask, &mut HashMap::new());
+        }
+        partitionings.rewind();
+        Ok(partitionings)
+    }
+
+    /// Restarts the iteration from the first partitioning, reusing the table.
+    pub fn rewind(&mut self) {
+        self.stack.clear();
+        let full_mask = self.most.len() - 1;
+        if full_mask == 0 {
+            // Without non-zero items the only partitioning has an empty
+            // partition of them.
+            self.stack.push(Frame {
+                mask: 0,
+                partitions: vec![0],
+                next: 0,
+            });
+        } else if self.most[full_mask] != UNBALANCED {
+            self.stack.push(self.frame(full_mask));
+        }
+    }
+
+    /// Number of partitionings yielded over the whole iteration, including
+    /// any that have already been yielded.
+    pub fn total_len(&self) -> u64 {
+        self.total_len
+    }
+
+    /// Balanced subsets of `mask` containing its lowest item.
+    fn candidates(&self, mask: usize) -> impl Iterator<Item = usize> + '_ {
+        let lowest = mask & mask.wrapping_neg();
+        let rest = mask & !lowest;
+        iter::successors(Some(rest), move |&submask| {
+            if submask == 0 {
+                None
+            } else {
+                Some((submask - 1) & rest)
+            }
+        })
+        .map(move |submask| submask | lowest)
+        .filter(move |&partition| self.balanced[partition])
+    }
+
+    /// Partitions that can be split off `mask` while keeping the most
+    /// partitions in the remainder.
+    fn partitions_of(&self, mask: usize) -> impl Iterator<Item = usize> + '_ {
+        let target = self.most[mask];
+        self.candidates(mask).filter(move |&partition| {
+            let remaining = self.most[mask & !partition];
+            remaining != UNBALANCED && remaining + 1 == target
+        })
+    }
+
+    fn frame(&self, mask: usize) -> Frame {
+        Frame {
+            mask,
+            partitions: self.partitions_of(mask).collect(),
+            next: 0,
+        }
+    }
+
+    /// Counts the partitionings of `mask` with the most partitions, memoising
+    /// the counts of the remaining masks.
+    fn count_from(&self, mask: usize, counts: &mut HashMap<usize, u64>) -> u64 {
+        if mask == 0 {
+            return 1;
+        }
+        if let Some(&count) = counts.get(&mask) {
+            return count;
+        }
+        let count = self
+            .partitions_of(mask)
+            .map(|partition| self.count_from(mask & !partition, counts))
+            .sum();
+        counts.insert(mask, count);
+        count
+    }
+}
+
+impl Iterator for ExhaustivePartitionings {
+    type Item = Vec<Vec<usize>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let masks = loop {
+            let frame = self.stack.last_mut()?;
+            let Some(&partition) = frame.partitions.get(frame.next) else {
+                self.stack.pop();
+                continue;
+            };
+            frame.next += 1;
+            let remaining = frame.mask & !partition;
+            if remaining == 0 {
+                break self
+                    .stack
+                    .iter()
+                    .map(|frame| frame.partitions[frame.next - 1])
+                    .collect::<Vec<_>>();
+            }
+            let frame = self.frame(remaining);
+            self.stack.push(frame);
+        };
+        Some(
+            masks
+                .into_iter()
+                // Leave out the empty partition when there are no non-zero
+                // items.
+                .filter(|&mask| mask != 0)
+                .map(|mask| {
+                    (0..self.indices.len())
+                        .filter(|i| (mask >> i) & 1 == 1)
+                        .map(|i| self.indices[i])
+                        .collect()
+                })
+                .chain(self.zero_indices.iter().map(|&index| vec![index]))
+                .collect(),
+        )
+    }
+}
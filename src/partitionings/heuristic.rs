@@ -0,0 +1,326 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use super::amount::checked_sum;
+use super::{Amount, PartitioningError, ZeroSumPartitioningIndices};
+
+/// Largest number of items in a pair of partitions that the local search will
+/// try to repartition exactly.
+const LOCAL_SEARCH_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeuristicPartitioning<'a, T> {
+    /// Partitioning where each partition sums to zero.
+    pub partitioning: Vec<Vec<&'a T>>,
+    /// Number of partitions that no partitioning can exceed.
+    pub upper_bound: usize,
+}
+
+impl<'a, T> HeuristicPartitioning<'a, T> {
+    /// Whether the partitioning reaches the upper bound, so no partitioning
+    /// has more partitions.
+    pub fn is_proven_optimal(&self) -> bool {
+        self.partitioning.len() == self.upper_bound
+    }
+}
+
+/// Finds a partitioning with many zero sum partitions in polynomial time, for
+/// sets too large for an exact search. Returns `None` if the set doesn't sum
+/// to zero, or an error if summing the set overflows.
+///
+/// Exact opposites are paired first, since some partitioning with the most
+/// partitions always has them paired. Then zero sum subsets of three and four
+/// items are found by hashing sums of single items and pairs. The items left
+/// over form a single partition. Finally, a local search repeatedly takes two
+/// small partitions and repartitions their items exactly, keeping the result
+/// when it has more partitions.
+pub fn heuristic_zero_sum_partitioning<T>(
+    set: &[T],
+) -> Result<Option<HeuristicPartitioning<'_, T>>, PartitioningError>
+where
+    T: Amount + Ord + Hash,
+{
+    let Some((partitioning, upper_bound)) = heuristic_zero_sum_partitioning_indices(set)? else {
+        return Ok(None);
+    };
+    Ok(Some(HeuristicPartitioning {
+        partitioning: partitioning
+            .into_iter()
+            .map(|partition| partition.into_iter().map(|i| &set[i]).collect())
+            .collect(),
+        upper_bound,
+    }))
+}
+
+/// Partitioning as indices into the set, along with the upper bound on the
+/// number of partitions.
+pub type HeuristicPartitioningIndices = (Vec<Vec<usize>>, usize);
+
+/// Same as [`heuristic_zero_sum_partitioning`] but returns the partitioning as
+/// indices into the set, along with the upper bound.
+pub fn heuristic_zero_sum_partitioning_indices<T>(
+    set: &[T],
+) -> Result<Option<HeuristicPartitioningIndices>, PartitioningError>
+where
+    T: Amount + Ord + Hash,
+{
+    let values = set;
+    if !checked_sum(values)?.is_zero() {
+        return Ok(None);
+    }
+    let mut partitioning: Vec<Vec<usize>> = vec![];
+    let mut remaining: Vec<usize> = vec![];
+    for (index, value) in values.iter().enumerate() {
+        if value.is_zero() {
+            partitioning.push(vec![index]);
+        } else {
+            remaining.push(index);
+        }
+    }
+    let upper_bound = partitioning.len() + upper_bound(values, &remaining);
+
+    partitioning.extend(take_opposite_pairs(values, &mut remaining));
+    partitioning.extend(take_triples(values, &mut remaining));
+    partitioning.extend(take_quadruples(values, &mut remaining));
+    if !remaining.is_empty() {
+        partitioning.push(remaining);
+    }
+    improve(values, &mut partitioning);
+    Ok(Some((partitioning, upper_bound)))
+}
+
+/// Bound on the number of partitions of the non-zero items at `indices`.
+///
+/// Each partition needs at least one positive and one negative item, and a
+/// partition of two items must be a pair of exact opposites. Every other
+/// partition has at least three items.
+fn upper_bound<T: Amount + Ord + Hash>(values: &[T], indices: &[usize]) -> usize {
+    let zero = T::zero();
+    let positives = indices.iter().filter(|&&i| values[i] > zero).count();
+    let negatives = indices.len() - positives;
+    let mut counts: HashMap<T, (usize, usize)> = HashMap::new();
+    for &i in indices {
+        if values[i] > zero {
+            counts.entry(values[i].clone()).or_default().0 += 1;
+        } else if let Some(opposite) = values[i].checked_neg() {
+            counts.entry(opposite).or_default().1 += 1;
+        }
+    }
+    let pairs: usize = counts.values().map(|&(a, b)| a.min(b)).sum();
+    positives
+        .min(negatives)
+        .min(pairs + (indices.len() - 2 * pairs) / 3)
+}
+
+/// Negated sum of the amounts, or `None` if it overflows.
+fn opposite<T: Amount>(a: &T, b: &T) -> Option<T> {
+    a.checked_add(b)?.checked_neg()
+}
+
+/// Removes pairs of items with opposite values from `remaining`.
+fn take_opposite_pairs<T: Amount + Hash + Eq>(
+    values: &[T],
+    remaining: &mut Vec<usize>,
+) -> Vec<Vec<usize>> {
+    let mut unmatched: HashMap<T, Vec<usize>> = HashMap::new();
+    let mut pairs = vec![];
+    for &i in remaining.iter() {
+        let matched = values[i]
+            .checked_neg()
+            .and_then(|opposite| unmatched.get_mut(&opposite))
+            .and_then(Vec::pop);
+        match matched {
+            Some(j) => pairs.push(vec![j, i]),
+            None => unmatched.entry(values[i].clone()).or_default().push(i),
+        }
+    }
+    remaining.retain(|i| !pairs.iter().any(|pair| pair.contains(i)));
+    pairs
+}
+
+/// Removes zero sum triples from `remaining` by looking up the negated sum of
+/// each pair among the single items. Pairs whose sum overflows are skipped.
+fn take_triples<T: Amount + Hash + Eq>(
+    values: &[T],
+    remaining: &mut Vec<usize>,
+) -> Vec<Vec<usize>> {
+    let mut triples = vec![];
+    'search: loop {
+        let mut by_value: HashMap<&T, Vec<usize>> = HashMap::new();
+        for &i in remaining.iter() {
+            by_value.entry(&values[i]).or_default().push(i);
+        }
+        for (a, &i) in remaining.iter().enumerate() {
+            for &j in &remaining[(a + 1)..] {
+                let candidates =
+                    opposite(&values[i], &values[j]).and_then(|opposite| by_value.get(&opposite));
+                if let Some(&k) = candidates.and_then(|c| c.iter().find(|&&k| k != i && k != j)) {
+                    let mut triple = vec![i, j, k];
+                    triple.sort();
+                    remaining.retain(|index| !triple.contains(index));
+                    triples.push(triple);
+                    continue 'search;
+                }
+            }
+        }
+        return triples;
+    }
+}
+
+/// Removes zero sum quadruples from `remaining` by looking up the negated sum
+/// of each pair among the sums of disjoint pairs. Pairs whose sum overflows
+/// are skipped.
+fn take_quadruples<T: Amount + Hash + Eq>(
+    values: &[T],
+    remaining: &mut Vec<usize>,
+) -> Vec<Vec<usize>> {
+    let mut quadruples = vec![];
+    'search: loop {
+        let mut by_sum: HashMap<T, Vec<(usize, usize)>> = HashMap::new();
+        for (a, &i) in remaining.iter().enumerate() {
+            for &j in &remaining[(a + 1)..] {
+                let Some(sum) = values[i].checked_add(&values[j]) else {
+                    continue;
+                };
+                let pairs = by_sum.entry(sum).or_default();
+                if pairs.len() < 4 {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        for (a, &i) in remaining.iter().enumerate() {
+            for &j in &remaining[(a + 1)..] {
+                let candidates =
+                    opposite(&values[i], &values[j]).and_then(|opposite| by_sum.get(&opposite));
+                let disjoint = candidates.and_then(|pairs| {
+                    pairs
+                        .iter()
+                        .find(|(k, l)| ![i, j].contains(k) && ![i, j].contains(l))
+                });
+                if let Some(&(k, l)) = disjoint {
+                    let mut quadruple = vec![i, j, k, l];
+                    quadruple.sort();
+                    remaining.retain(|index| !quadruple.contains(index));
+                    quadruples.push(quadruple);
+                    continue 'search;
+                }
+            }
+        }
+        return quadruples;
+    }
+}
+
+/// Repartitions pairs of partitions exactly while doing so gives more
+/// partitions.
+fn improve<T: Amount>(values: &[T], partitioning: &mut Vec<Vec<usize>>) {
+    for partition in partitioning.iter_mut() {
+        partition.sort();
+    }
+    // Pairs of partitions that are already known not to improve.
+    let mut checked: HashSet<(Vec<usize>, Vec<usize>)> = HashSet::new();
+    'search: loop {
+        for a in 0..partitioning.len() {
+            for b in a..partitioning.len() {
+                let current_len = if b == a { 1 } else { 2 };
+                let len = partitioning[a].len() + if b == a { 0 } else { partitioning[b].len() };
+                // Splitting into more partitions needs at least two items for
+                // each partition and three for one of them.
+                if len > LOCAL_SEARCH_LEN || len < 2 * current_len + 1 {
+                    continue;
+                }
+                let key = (partitioning[a].clone(), partitioning[b].clone());
+                if !checked.insert(key) {
+                    continue;
+                }
+                let mut items = partitioning[a].clone();
+                if b != a {
+                    items.extend(&partitioning[b]);
+                }
+                let item_values: Vec<_> = items.iter().map(|&i| values[i].clone()).collect();
+                let best = ZeroSumPartitioningIndices::new(&item_values)
+                    .ok()
+                    .and_then(|mut partitionings| partitionings.next());
+                if let Some(best) = best.filter(|best| best.len() > current_len) {
+                    if b != a {
+                        partitioning.remove(b);
+                    }
+                    partitioning.remove(a);
+                    partitioning.extend(best.into_iter().map(|partition| {
+                        let mut partition: Vec<_> =
+                            partition.into_iter().map(|i| items[i]).collect();
+                        partition.sort();
+                        partition
+                    }));
+                    continue 'search;
+                }
+            }
+        }
+        return;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(values: &[i32], partitioning: &[Vec<usize>]) {
+        let mut indices: Vec<_> = partitioning.iter().flatten().copied().collect();
+        indices.sort();
+        assert_eq!(indices, (0..values.len()).collect::<Vec<_>>());
+        for partition in partitioning {
+            assert_eq!(partition.iter().map(|&i| values[i]).sum::<i32>(), 0);
+        }
+    }
+
+    #[test]
+    fn test_heuristic_zero_sum_partitioning_pairs() {
+        let set: Vec<_> = (1..=100).flat_map(|i| [i, -i]).collect();
+        let result = heuristic_zero_sum_partitioning(&set).unwrap().unwrap();
+        assert_eq!(result.partitioning.len(), 100);
+        assert!(result.is_proven_optimal());
+    }
+
+    #[test]
+    fn test_heuristic_zero_sum_partitioning_large_group() {
+        // Triples with no exact opposites alongside a zero and a group that
+        // can't be split.
+        let mut values: Vec<_> = (1..=30)
+            .flat_map(|i| [10 * i + 1, 10 * i + 2, -(20 * i + 3)])
+            .collect();
+        values.extend([0, 7, 11, -5, -13]);
+        let (partitioning, upper_bound) = heuristic_zero_sum_partitioning_indices(&values)
+            .unwrap()
+            .unwrap();
+        assert_valid(&values, &partitioning);
+        assert_eq!(partitioning.len(), 32);
+        assert_eq!(upper_bound, 32);
+        assert!(partitioning.len() <= upper_bound);
+    }
+
+    #[test]
+    fn test_improve() {
+        let values = [1, 2, -3, 5, -2, -3];
+        let mut partitioning = vec![(0..values.len()).collect()];
+        improve(&values, &mut partitioning);
+        assert_valid(&values, &partitioning);
+        assert_eq!(partitioning.len(), 2);
+    }
+
+    #[test]
+    fn test_heuristic_zero_sum_partitioning_unbalanced() {
+        assert_eq!(heuristic_zero_sum_partitioning(&[1, 2, -4]), Ok(None));
+    }
+
+    #[test]
+    fn test_heuristic_zero_sum_partitioning_overflow() {
+        assert_eq!(
+            heuristic_zero_sum_partitioning(&[i32::MAX, 1, -1]),
+            Err(PartitioningError::Overflow)
+        );
+        // Pairs whose sums overflow are skipped rather than wrapping.
+        let (partitioning, _) = heuristic_zero_sum_partitioning_indices(&[i8::MIN, 100, 28, 1, -1])
+            .unwrap()
+            .unwrap();
+        assert_eq!(partitioning, [vec![3, 4], vec![0, 1, 2]]);
+    }
+}
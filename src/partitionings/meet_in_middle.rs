@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::amount::checked_sum;
+use super::exhaustive::subset_sums;
+use super::{Amount, PartitioningError, MAX_SEARCH_LEN};
+
+/// Returns an iterator over the zero sum subsets of the set that don't
+/// contain a smaller zero sum subset, yielding sorted indices into the set.
+///
+/// The set is split into halves and only the sums of the subsets of each half
+/// are kept, so memory grows with the square root of the number of subsets
+/// rather than with the number of subsets.
+pub fn minimal_zero_sum_subsets<T>(set: &[T]) -> Result<MinimalZeroSumSubsets<T>, PartitioningError>
+where
+    T: Amount + Eq + Hash,
+{
+    if set.len() > MAX_SEARCH_LEN {
+        return Err(PartitioningError::TooManyItems {
+            len: set.len(),
+            max: MAX_SEARCH_LEN,
+        });
+    }
+    checked_sum(set)?;
+    let left_len = set.len() / 2;
+    let left_sums = subset_sums(&set[..left_len])?;
+    let right_sums = subset_sums(&set[left_len..])?;
+    let mut right_masks: HashMap<T, Vec<usize>> = HashMap::new();
+    for (mask, sum) in right_sums.iter().enumerate() {
+        right_masks.entry(sum.clone()).or_default().push(mask);
+    }
+    Ok(MinimalZeroSumSubsets {
+        left_len,
+        left_sums,
+        right_sums,
+        right_masks,
+        left_mask: 0,
+        matches: vec![],
+    })
+}
+
+/// Iterator over the minimal zero sum subsets of a set, see
+/// [`minimal_zero_sum_subsets`].
+pub struct MinimalZeroSumSubsets<T> {
+    left_len: usize,
+    /// Sum of every subset of the first half, indexed by bit mask.
+    left_sums: Vec<T>,
+    /// Sum of every subset of the second half, indexed by bit mask.
+    right_sums: Vec<T>,
+    /// Subsets of the second half by their sum.
+    right_masks: HashMap<T, Vec<usize>>,
+    /// Next subset of the first half to match.
+    left_mask: usize,
+    /// Subsets of the second half matching the previous subset of the first
+    /// half that are still to be checked.
+    matches: Vec<usize>,
+}
+
+impl<T> MinimalZeroSumSubsets<T>
+where
+    T: Amount + Eq + Hash,
+{
+    /// Whether the only zero sum subsets of the subset made up of `left` and
+    /// `right` are itself and the empty subset.
+    fn is_minimal(&self, left: usize, right: usize) -> bool {
+        let mut right_counts: HashMap<&T, usize> = HashMap::new();
+        for mask in submasks(right) {
+            *right_counts.entry(&self.right_sums[mask]).or_default() += 1;
+        }
+        let mut zero_sum_len = 0;
+        for mask in submasks(left) {
+            if let Some(count) = self.left_sums[mask]
+                .checked_neg()
+                .and_then(|target| right_counts.get(&target))
+            {
+                zero_sum_len += count;
+                if zero_sum_len > 2 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl<T> Iterator for MinimalZeroSumSubsets<T>
+where
+    T: Amount + Eq + Hash,
+{
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(right) = self.matches.pop() {
+                let left = self.left_mask - 1;
+                if (left != 0 || right != 0) && self.is_minimal(left, right) {
+                    return Some(
+                        bits(left)
+                            .chain(bits(right).map(|i| i + self.left_len))
+                            .collect(),
+                    );
+                }
+            }
+            let sum = self.left_sums.get(self.left_mask)?;
+            if let Some(masks) = sum
+                .checked_neg()
+                .and_then(|target| self.right_masks.get(&target))
+            {
+                // Popped from the back, so reversed to match in mask order.
+                self.matches = masks.iter().rev().copied().collect();
+            }
+            self.left_mask += 1;
+        }
+    }
+}
+
+/// Every subset of the mask, from the mask itself down to the empty one.
+fn submasks(mask: usize) -> impl Iterator<Item = usize> {
+    let mut next = Some(mask);
+    std::iter::from_fn(move || {
+        let current = next?;
+        next = (current != 0).then(|| (current - 1) & mask);
+        Some(current)
+    })
+}
+
+/// Indices of the bits set in the mask, from lowest to highest.
+fn bits(mut mask: usize) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        (mask != 0).then(|| {
+            let lowest = mask.trailing_zeros() as usize;
+            mask &= mask - 1;
+            lowest
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimal_zero_sum_subsets() {
+        let set = [-6, -2, -1, 2, 3, 4, 0];
+        let mut subsets: Vec<_> = minimal_zero_sum_subsets(&set).unwrap().collect();
+        subsets.sort();
+        // Subsets such as [-2, 2, 0] contain a smaller zero sum subset so
+        // aren't included.
+        assert_eq!(
+            subsets,
+            [
+                vec![0, 2, 4, 5],
+                vec![0, 3, 5],
+                vec![1, 2, 4],
+                vec![1, 3],
+                vec![6],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minimal_zero_sum_subsets_overflow() {
+        assert!(matches!(
+            minimal_zero_sum_subsets(&[i32::MAX, i32::MAX, -1]),
+            Err(PartitioningError::Overflow)
+        ));
+    }
+}
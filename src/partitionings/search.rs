@@ -0,0 +1,176 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::{check_balanced, Amount, PartitioningError, ZeroSumPartitioningIndices};
+
+/// Number of steps between checks of the deadline and cancellation token.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// Limits on how long a partition search may run before it settles for the
+/// best partitioning found so far.
+#[derive(Debug, Clone, Default)]
+pub struct SearchConfig {
+    /// Time after which the search stops.
+    ///
+    /// `Instant::now` panics on `wasm32-unknown-unknown`, so use `max_steps`
+    /// there instead.
+    pub deadline: Option<Instant>,
+    /// Number of subsets the search may visit before it stops.
+    pub max_steps: Option<u64>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+/// Shared flag for stopping a running search from elsewhere.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Steps taken so far against a search configuration.
+pub(super) struct Budget<'a> {
+    config: &'a SearchConfig,
+    steps: u64,
+}
+
+impl<'a> Budget<'a> {
+    pub(super) fn new(config: &'a SearchConfig) -> Self {
+        Self { config, steps: 0 }
+    }
+
+    /// Takes a step, returning whether the search may continue.
+    pub(super) fn step(&mut self) -> bool {
+        self.steps += 1;
+        if self
+            .config
+            .max_steps
+            .map_or(false, |max_steps| self.steps > max_steps)
+        {
+            return false;
+        }
+        if self.steps % CHECK_INTERVAL == 0 {
+            let past_deadline = self
+                .config
+                .deadline
+                .map_or(false, |deadline| Instant::now() >= deadline);
+            let cancelled = self
+                .config
+                .cancellation
+                .as_ref()
+                .map_or(false, CancellationToken::is_cancelled);
+            if past_deadline || cancelled {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchOutcome<'a, T> {
+    /// Partitioning with the most zero sum partitions found.
+    pub partitioning: Vec<Vec<&'a T>>,
+    /// Whether the search finished, so no partitioning has more partitions.
+    pub proven_optimal: bool,
+}
+
+/// Searches for a partitioning with the most zero sum partitions, stopping
+/// early with the best partitioning found so far if the configured budget
+/// runs out.
+///
+/// Returns [`PartitioningError::Unbalanced`] if the set doesn't sum to zero.
+pub fn search_longest_zero_sum_partitioning<'a, T>(
+    set: &'a [T],
+    config: &SearchConfig,
+) -> Result<SearchOutcome<'a, T>, PartitioningError>
+where
+    T: Amount,
+{
+    check_balanced(set)?;
+    let mut partitionings = ZeroSumPartitioningIndices::with_config(set, config)?;
+    // A balanced set has at least the partitioning into a single partition.
+    let partitioning = partitionings.next().unwrap_or_default();
+    Ok(SearchOutcome {
+        partitioning: partitioning
+            .into_iter()
+            .map(|partition| partition.into_iter().map(|i| &set[i]).collect())
+            .collect(),
+        proven_optimal: partitionings.is_proven_optimal(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_longest_zero_sum_partitioning_unlimited() {
+        let set = [-6, -2, -1, 2, 3, 4];
+        let outcome = search_longest_zero_sum_partitioning(&set, &Default::default()).unwrap();
+        assert!(outcome.proven_optimal);
+        assert_eq!(outcome.partitioning.len(), 2);
+    }
+
+    #[test]
+    fn test_search_longest_zero_sum_partitioning_out_of_steps() {
+        let set = [1, -1, 2, -2, 3, -3, 4, -4];
+        let config = SearchConfig {
+            max_steps: Some(16),
+            ..Default::default()
+        };
+        let outcome = search_longest_zero_sum_partitioning(&set, &config).unwrap();
+        assert!(!outcome.proven_optimal);
+        // Only subsets of the first four items have been visited, so the
+        // rest are left in a single partition.
+        assert_eq!(
+            outcome.partitioning,
+            [vec![&1, &-1], vec![&2, &-2], vec![&3, &-3, &4, &-4]]
+        );
+    }
+
+    #[test]
+    fn test_search_longest_zero_sum_partitioning_cancelled() {
+        let set = [1, -1, 2, -2];
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let config = SearchConfig {
+            cancellation: Some(cancellation),
+            ..Default::default()
+        };
+        let outcome = search_longest_zero_sum_partitioning(&set, &config).unwrap();
+        // Cancellation is only checked periodically, so a small search still
+        // finishes.
+        assert!(outcome.proven_optimal);
+        let set: Vec<_> = (1..=6).flat_map(|i| [i, -i]).collect();
+        let outcome = search_longest_zero_sum_partitioning(&set, &config).unwrap();
+        assert!(!outcome.proven_optimal);
+        assert!(!outcome.partitioning.is_empty());
+    }
+
+    #[test]
+    fn test_search_longest_zero_sum_partitioning_unbalanced() {
+        let config = SearchConfig {
+            max_steps: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            search_longest_zero_sum_partitioning(&[1, 2], &config),
+            Err(PartitioningError::Unbalanced {
+                net: Some(3),
+                entries: vec![0, 1],
+            })
+        );
+    }
+}
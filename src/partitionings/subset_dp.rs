@@ -0,0 +1,703 @@
+use std::collections::HashMap;
+use std::iter;
+
+use super::amount::checked_sum;
+use super::search::{Budget, SearchConfig};
+use super::{check_balanced, Amount, PartitioningError};
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+mod parallel;
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub use parallel::longest_zero_sum_partitionings_par;
+
+/// Largest number of non-zero items that the subset dynamic programming will
+/// attempt.
+///
+/// The tables hold an entry for every subset of the non-zero items so memory
+/// doubles with each item beyond this.
+pub const MAX_DP_LEN: usize = 24;
+
+/// Same as [`super::longest_zero_sum_partitionings`] but finds the partitionings
+/// with dynamic programming over subset bit masks instead of enumerating every
+/// combination of zero sum subsets.
+///
+/// Returns [`PartitioningError::Unbalanced`] if the set doesn't sum to zero.
+pub fn longest_zero_sum_partitionings_dp<T>(
+    set: &[T],
+) -> Result<Vec<Vec<Vec<&T>>>, PartitioningError>
+where
+    T: Amount,
+{
+    Ok(zero_sum_partitionings_iter(set)?.collect())
+}
+
+/// Counts the partitionings that [`longest_zero_sum_partitionings_dp`] would
+/// return without building any of them.
+pub fn count_longest_zero_sum_partitionings<T>(set: &[T]) -> Result<u64, PartitioningError>
+where
+    T: Amount,
+{
+    check_balanced(set)?;
+    Ok(ZeroSumPartitioningIndices::new(set)?.total_len())
+}
+
+/// Returns an iterator over the same partitionings as
+/// [`longest_zero_sum_partitionings_dp`], reconstructing each one only when it
+/// is requested.
+pub fn zero_sum_partitionings_iter<'a, T>(
+    set: &'a [T],
+) -> Result<ZeroSumPartitionings<'a, T>, PartitioningError>
+where
+    T: Amount,
+{
+    check_balanced(set)?;
+    Ok(ZeroSumPartitionings {
+        set,
+        indices: ZeroSumPartitioningIndices::new(set)?,
+    })
+}
+
+/// Iterator over the partitionings with the most zero sum partitions, yielding
+/// references to the items of the set.
+pub struct ZeroSumPartitionings<'a, T> {
+    set: &'a [T],
+    indices: ZeroSumPartitioningIndices<T>,
+}
+
+impl<'a, T: Amount> Iterator for ZeroSumPartitionings<'a, T> {
+    type Item = Vec<Vec<&'a T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indices.next().map(|partitioning| {
+            partitioning
+                .into_iter()
+                .map(|partition| partition.into_iter().map(|i| &self.set[i]).collect())
+                .collect()
+        })
+    }
+}
+
+/// Iterator over the partitionings with the most zero sum partitions, yielding
+/// indices into the set.
+///
+/// Unlike [`ZeroSumPartitionings`], this doesn't borrow the set so it can be
+/// kept alongside it.
+///
+/// For each subset of the non-zero items, the table holds the most zero sum
+/// prefixes over all orders of its items. For a zero sum subset, that is the
+/// maximum number of zero sum partitions it can be split into. Partitionings
+/// are reconstructed by depth first search, repeatedly splitting off a
+/// partition that contains the lowest remaining item.
+///
+/// If the table couldn't be completed within the search budget, only the best
+/// partitioning found is yielded.
+pub struct ZeroSumPartitioningIndices<T> {
+    indices: Vec<usize>,
+    zero_indices: Vec<usize>,
+    table: SubsetTable<T>,
+    stack: Vec<Frame>,
+    /// Partitioning to yield before searching, for when there is nothing to
+    /// search or the search was cut short.
+    pending: Option<Vec<Vec<usize>>>,
+    /// Where the iteration starts, so that it can be rewound.
+    start: Start,
+    proven_optimal: bool,
+    total_len: u64,
+}
+
+/// Start of the iteration: a single partitioning or a search of the full mask.
+#[derive(Clone)]
+enum Start {
+    Pending(Vec<Vec<usize>>),
+    Search(usize),
+    Empty,
+}
+
+/// Partitions that can be split off a remaining mask in the depth first
+/// search, and the next one to try.
+struct Frame {
+    mask: usize,
+    partitions: Vec<usize>,
+    next: usize,
+}
+
+/// Partition search whose subset table can be filled a bit at a time, for
+/// reporting progress or giving other work a turn in between.
+#[derive(Clone)]
+pub struct PartitioningSearch<T> {
+    indices: Vec<usize>,
+    zero_indices: Vec<usize>,
+    table: SubsetTable<T>,
+    /// Whether the set sums to zero, so that it can be partitioned at all.
+    balanced: bool,
+}
+
+impl<T: Amount> PartitioningSearch<T> {
+    pub fn new(set: &[T]) -> Result<Self, PartitioningError> {
+        let (zero_indices, indices): (Vec<_>, Vec<_>) =
+            (0..set.len()).partition(|&index| set[index].is_zero());
+        if indices.len() > MAX_DP_LEN {
+            return Err(PartitioningError::TooManyItems {
+                len: indices.len(),
+                max: MAX_DP_LEN,
+            });
+        }
+        let values: Vec<_> = indices.iter().map(|&index| set[index].clone()).collect();
+        let balanced = checked_sum(&values)?.is_zero();
+        Ok(Self {
+            indices,
+            zero_indices,
+            table: SubsetTable::new(values),
+            balanced,
+        })
+    }
+
+    /// Same as [`Self::new`] but takes the subsets of the items shared with a
+    /// previous complete search from its table, so that only subsets
+    /// containing the other items are left to fill.
+    ///
+    /// Items are matched by value, so an item whose amount changed is treated
+    /// as removed and added again.
+    pub fn reusing(set: &[T], previous: &Self) -> Result<Self, PartitioningError>
+    where
+        T: Eq,
+    {
+        let mut search = Self::new(set)?;
+        if !previous.is_complete() {
+            return Ok(search);
+        }
+        let mut unmatched: Vec<_> = previous.table.values.iter().map(Some).collect();
+        let mut kept = vec![];
+        let mut added = vec![];
+        for (position, value) in search.table.values.iter().enumerate() {
+            match unmatched.iter().position(|&other| other == Some(value)) {
+                Some(previous_position) => {
+                    unmatched[previous_position] = None;
+                    kept.push((position, previous_position));
+                }
+                None => added.push(position),
+            }
+        }
+
+        // Shared items take the lowest bits so their subsets come first in
+        // the table.
+        let order: Vec<_> = kept
+            .iter()
+            .map(|&(position, _)| position)
+            .chain(added)
+            .collect();
+        search.indices = order.iter().map(|&i| search.indices[i]).collect();
+        search.table.values = order
+            .iter()
+            .map(|&i| search.table.values[i].clone())
+            .collect();
+        let kept_len = 1 << kept.len();
+        let full_mask = search.table.sums.len() - 1;
+        let mut previous_masks = vec![0; kept_len];
+        for mask in 1..kept_len {
+            let lowest = mask.trailing_zeros() as usize;
+            previous_masks[mask] = previous_masks[mask & (mask - 1)] | 1 << kept[lowest].1;
+            let table = &mut search.table;
+            table.sums[mask] = previous.table.sums[previous_masks[mask]].clone();
+            table.prefixes[mask] = previous.table.prefixes[previous_masks[mask]];
+            if table.sums[mask].is_zero()
+                && mask != full_mask
+                && table.prefixes[mask] > table.prefixes[table.best_mask]
+            {
+                table.best_mask = mask;
+            }
+        }
+        search.table.filled = kept_len;
+        Ok(search)
+    }
+
+    /// Whether the table has been filled, so that finishing the search gives
+    /// the optimal partitionings.
+    pub fn is_complete(&self) -> bool {
+        self.table.filled == self.table.sums.len()
+    }
+
+    /// Continues filling the table within the budget of the given search
+    /// configuration, returning whether the table is complete or an error if
+    /// the sum of a subset overflows.
+    pub fn advance(&mut self, config: &SearchConfig) -> Result<bool, PartitioningError> {
+        self.table.fill(&mut Budget::new(config))
+    }
+
+    /// Number of subsets visited so far.
+    pub fn steps(&self) -> u64 {
+        self.table.filled as u64 - 1
+    }
+
+    /// Number of subsets to visit for the search to be complete.
+    pub fn total_steps(&self) -> u64 {
+        self.table.sums.len() as u64 - 1
+    }
+
+    /// Stops the search, returning the optimal partitionings if the table is
+    /// complete or otherwise the best partitioning found so far.
+    pub fn finish(self) -> ZeroSumPartitioningIndices<T> {
+        let full_mask = self.table.sums.len() - 1;
+        let mut partitionings = ZeroSumPartitioningIndices {
+            indices: self.indices,
+            zero_indices: self.zero_indices,
+            table: self.table,
+            stack: vec![],
+            pending: None,
+            start: Start::Empty,
+            proven_optimal: true,
+            total_len: 0,
+        };
+        if !self.balanced {
+            return partitionings;
+        }
+        if full_mask == 0 {
+            partitionings.start = Start::Pending(partitionings.partitioning([]));
+            partitionings.total_len = 1;
+        } else if partitionings.table.is_filled(full_mask) {
+            partitionings.start = Start::Search(full_mask);
+            partitionings.total_len = partitionings.table.count(full_mask, &mut HashMap::new());
+        } else {
+            // Take the best partitioning of a completed zero sum subset and
+            // leave the remaining items in a single partition.
+            let best_mask = partitionings.table.best_mask;
+            let mut masks = vec![];
+            if best_mask != 0 {
+                partitionings
+                    .stack
+                    .push(partitionings.table.frame(best_mask));
+                masks.extend(partitionings.next_masks().unwrap_or_default());
+                partitionings.stack.clear();
+            }
+            masks.push(full_mask & !best_mask);
+            partitionings.start = Start::Pending(partitionings.partitioning(masks));
+            partitionings.proven_optimal = false;
+            partitionings.total_len = 1;
+        }
+        partitionings.rewind();
+        partitionings
+    }
+}
+
+impl<T: Amount> ZeroSumPartitioningIndices<T> {
+    pub fn new(set: &[T]) -> Result<Self, PartitioningError> {
+        Self::with_config(set, &Default::default())
+    }
+
+    /// Builds the table within the budget of the given search configuration.
+    pub fn with_config(set: &[T], config: &SearchConfig) -> Result<Self, PartitioningError> {
+        let mut search = PartitioningSearch::new(set)?;
+        search.advance(config)?;
+        Ok(search.finish())
+    }
+
+    /// Whether the search was completed so that every partitioning yielded has
+    /// the most zero sum partitions possible.
+    pub fn is_proven_optimal(&self) -> bool {
+        self.proven_optimal
+    }
+
+    /// Number of partitionings yielded over the whole iteration, including
+    /// any that have already been yielded.
+    pub fn total_len(&self) -> u64 {
+        self.total_len
+    }
+
+    /// Restarts the iteration from the first partitioning, reusing the table.
+    pub fn rewind(&mut self) {
+        self.stack.clear();
+        self.pending = None;
+        match &self.start {
+            Start::Pending(partitioning) => self.pending = Some(partitioning.clone()),
+            &Start::Search(mask) => self.stack.push(self.table.frame(mask)),
+            Start::Empty => {}
+        }
+    }
+
+    /// Advances the depth first search to the next complete partitioning,
+    /// returning its partition masks.
+    fn next_masks(&mut self) -> Option<Vec<usize>> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let Some(&partition) = frame.partitions.get(frame.next) else {
+                self.stack.pop();
+                continue;
+            };
+            frame.next += 1;
+            let remaining = frame.mask & !partition;
+            if remaining == 0 {
+                return Some(
+                    self.stack
+                        .iter()
+                        .map(|frame| frame.partitions[frame.next - 1])
+                        .collect(),
+                );
+            }
+            let frame = self.table.frame(remaining);
+            self.stack.push(frame);
+        }
+    }
+
+    /// Converts partition masks of the non-zero items into partitions of set
+    /// indices, with each zero item in its own partition.
+    fn partitioning<I>(&self, masks: I) -> Vec<Vec<usize>>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        masks
+            .into_iter()
+            .map(|mask| {
+                let mut partition: Vec<_> = (0..self.indices.len())
+                    .filter(|i| (mask >> i) & 1 == 1)
+                    .map(|i| self.indices[i])
+                    .collect();
+                // Items may be out of order in a table reused from an earlier
+                // search.
+                partition.sort();
+                partition
+            })
+            .chain(self.zero_indices.iter().map(|&index| vec![index]))
+            .collect()
+    }
+}
+
+impl<T: Amount> Iterator for ZeroSumPartitioningIndices<T> {
+    type Item = Vec<Vec<usize>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(partitioning) = self.pending.take() {
+            return Some(partitioning);
+        }
+        let masks = self.next_masks()?;
+        Some(self.partitioning(masks))
+    }
+}
+
+/// Subset sums and most zero sum prefixes indexed by subset bit mask.
+///
+/// Masks are filled in ascending order so every submask of a filled mask is
+/// also filled.
+#[derive(Clone)]
+struct SubsetTable<T> {
+    values: Vec<T>,
+    sums: Vec<T>,
+    prefixes: Vec<u8>,
+    /// Number of masks filled, starting from zero.
+    filled: usize,
+    /// Filled zero sum mask other than the full mask with the most zero sum
+    /// prefixes, or zero if there isn't one.
+    best_mask: usize,
+}
+
+impl<T: Amount> SubsetTable<T> {
+    fn new(values: Vec<T>) -> Self {
+        let len = 1 << values.len();
+        Self {
+            values,
+            sums: vec![T::zero(); len],
+            prefixes: vec![0; len],
+            filled: 1,
+            best_mask: 0,
+        }
+    }
+
+    /// Fills the table in ascending mask order until it is complete or the
+    /// budget runs out, returning whether it is complete.
+    fn fill(&mut self, budget: &mut Budget) -> Result<bool, PartitioningError> {
+        let len = self.sums.len();
+        while self.filled < len {
+            if !budget.step() {
+                return Ok(false);
+            }
+            let mask = self.filled;
+            let lowest = mask.trailing_zeros() as usize;
+            self.sums[mask] = self.sums[mask & (mask - 1)]
+                .checked_add(&self.values[lowest])
+                .ok_or(PartitioningError::Overflow)?;
+            let most_prefixes = (0..self.values.len())
+                .filter(|i| (mask >> i) & 1 == 1)
+                .map(|i| self.prefixes[mask & !(1 << i)])
+                .max()
+                .unwrap_or(0);
+            self.prefixes[mask] = most_prefixes + u8::from(self.sums[mask].is_zero());
+            if self.sums[mask].is_zero()
+                && mask != len - 1
+                && self.prefixes[mask] > self.prefixes[self.best_mask]
+            {
+                self.best_mask = mask;
+            }
+            self.filled += 1;
+        }
+        Ok(true)
+    }
+
+    fn is_filled(&self, mask: usize) -> bool {
+        mask < self.filled
+    }
+
+    fn frame(&self, mask: usize) -> Frame {
+        Frame {
+            mask,
+            partitions: self.partitions_of(mask).collect(),
+            next: 0,
+        }
+    }
+
+    /// Counts the partitionings of the zero sum `mask` with the most
+    /// partitions, memoising the counts of the remaining masks.
+    fn count(&self, mask: usize, counts: &mut HashMap<usize, u64>) -> u64 {
+        if mask == 0 {
+            return 1;
+        }
+        if let Some(&count) = counts.get(&mask) {
+            return count;
+        }
+        let count = self
+            .partitions_of(mask)
+            .map(|partition| self.count(mask & !partition, counts))
+            .sum();
+        counts.insert(mask, count);
+        count
+    }
+
+    /// Partitions containing the lowest item of the zero sum `mask` that can be
+    /// split off while keeping the most partitions in the remainder.
+    fn partitions_of(&self, mask: usize) -> impl Iterator<Item = usize> + '_ {
+        let lowest = mask & mask.wrapping_neg();
+        let rest = mask & !lowest;
+        let target = self.prefixes[mask];
+        // Walks the submasks of `rest` from largest to smallest, ending with
+        // zero.
+        iter::successors(Some(rest), move |&submask| {
+            if submask == 0 {
+                None
+            } else {
+                Some((submask - 1) & rest)
+            }
+        })
+        .map(move |submask| submask | lowest)
+        .filter(move |&partition| {
+            self.sums[partition].is_zero()
+                && self.prefixes[partition] == 1
+                && self.prefixes[mask & !partition] + 1 == target
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::exhaustive::{subset_sums, ExhaustivePartitionings};
+    use super::*;
+
+    /// Xorshift generator so the random tests are reproducible.
+    fn random_values(seed: &mut u64, len: usize, max: i32) -> Vec<i32> {
+        let mut values: Vec<_> = (0..len)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 7;
+                *seed ^= *seed << 17;
+                (*seed % (2 * max as u64 + 1)) as i32 - max
+            })
+            .collect();
+        // Balance the last value so that the set sums to zero.
+        let sum: i32 = values.iter().sum();
+        if let Some(last) = values.last_mut() {
+            *last -= sum;
+        }
+        values
+    }
+
+    /// Sorted partitionings of item indices so results can be compared
+    /// regardless of order.
+    fn canonical<T>(set: &[T], partitionings: Vec<Vec<Vec<&T>>>) -> Vec<Vec<Vec<usize>>> {
+        sorted(
+            partitionings
+                .into_iter()
+                .map(|partitioning| {
+                    partitioning
+                        .into_iter()
+                        .map(|partition| {
+                            partition
+                                .into_iter()
+                                .map(|item| set.iter().position(|x| std::ptr::eq(x, item)).unwrap())
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn sorted(mut partitionings: Vec<Vec<Vec<usize>>>) -> Vec<Vec<Vec<usize>>> {
+        for partitioning in &mut partitionings {
+            for partition in partitioning.iter_mut() {
+                partition.sort();
+            }
+            partitioning.sort();
+        }
+        partitionings.sort();
+        partitionings
+    }
+
+    /// Partitionings found by trying every split of every subset, which
+    /// shares nothing with the subset dynamic programming.
+    fn exhaustive(set: &[i32]) -> Vec<Vec<Vec<usize>>> {
+        let (zero_indices, indices): (Vec<_>, Vec<_>) =
+            (0..set.len()).partition(|&index| set[index] == 0);
+        let values: Vec<_> = indices.iter().map(|&index| set[index]).collect();
+        let balanced = subset_sums(&values)
+            .unwrap()
+            .into_iter()
+            .map(|sum| sum == 0)
+            .collect();
+        ExhaustivePartitionings::new(indices, zero_indices, balanced)
+            .unwrap()
+            .collect()
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_dp_single_result() {
+        let length = 10;
+        let set: Vec<_> = (0..length)
+            .flat_map(|i| vec![2_i32.pow(i), -2_i32.pow(i)])
+            .collect();
+        let partitionings = longest_zero_sum_partitionings_dp(&set).unwrap();
+        assert_eq!(partitionings.len(), 1);
+        assert_eq!(partitionings[0].len(), length as usize);
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_dp_with_multiple_results() {
+        let partitionings = longest_zero_sum_partitionings_dp(&[-6, -2, -1, 2, 3, 4]).unwrap();
+        assert_eq!(partitionings.len(), 2);
+        assert_eq!(partitionings[0].len(), 2);
+        assert_eq!(partitionings[1].len(), 2);
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_dp_unbalanced() {
+        let set = [-6, -2, 1, 2, 3, 4];
+        let unbalanced = Err(PartitioningError::Unbalanced {
+            net: Some(2),
+            entries: vec![2, 3, 4, 5],
+        });
+        assert_eq!(longest_zero_sum_partitionings_dp(&set), unbalanced);
+        assert_eq!(
+            count_longest_zero_sum_partitionings(&set),
+            unbalanced.clone().map(|_| 0)
+        );
+        assert_eq!(zero_sum_partitionings_iter(&set).err(), unbalanced.err());
+    }
+
+    #[test]
+    fn test_zero_sum_partitionings_iter() {
+        let set = [-6, -2, -1, 2, 3, 4, 0];
+        let mut partitionings = zero_sum_partitionings_iter(&set).unwrap();
+        let first = partitionings.next().unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[2], [&0]);
+        assert_eq!(partitionings.count(), 1);
+        assert_eq!(
+            ZeroSumPartitioningIndices::new(&[0, 0])
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [vec![vec![0], vec![1]]]
+        );
+    }
+
+    #[test]
+    fn test_count_longest_zero_sum_partitionings() {
+        assert_eq!(
+            count_longest_zero_sum_partitionings(&[-6, -2, -1, 2, 3, 4]),
+            Ok(2)
+        );
+        assert_eq!(count_longest_zero_sum_partitionings::<i32>(&[]), Ok(1));
+        assert_eq!(
+            count_longest_zero_sum_partitionings(&[1, 2]),
+            Err(PartitioningError::Unbalanced {
+                net: Some(3),
+                entries: vec![0, 1],
+            })
+        );
+        // Four people owing 1 each to four people owed 1 each can be paired
+        // in 4! ways.
+        assert_eq!(
+            count_longest_zero_sum_partitionings(&[1, 1, 1, 1, -1, -1, -1, -1]),
+            Ok(24)
+        );
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_dp_wide_amounts() {
+        let big = i64::MAX / 2;
+        let set = [big, -big, big, -big];
+        let partitionings = longest_zero_sum_partitionings_dp(&set).unwrap();
+        assert_eq!(partitionings.len(), 2);
+        assert_eq!(partitionings[0].len(), 2);
+        // Total fits but a subset sum overflows.
+        let set = [i32::MAX, -i32::MAX, 1, -1];
+        assert_eq!(
+            longest_zero_sum_partitionings_dp(&set),
+            Err(PartitioningError::Overflow)
+        );
+        assert_eq!(
+            count_longest_zero_sum_partitionings(&[i32::MAX, 1, -1]),
+            Err(PartitioningError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut partitionings = ZeroSumPartitioningIndices::new(&[-6, -2, -1, 2, 3, 4, 0]).unwrap();
+        let first: Vec<_> = partitionings.by_ref().collect();
+        assert_eq!(first.len(), 2);
+        partitionings.rewind();
+        assert_eq!(partitionings.collect::<Vec<_>>(), first);
+    }
+
+    #[test]
+    fn test_partitioning_search_in_steps() {
+        let set = [1, -1, 2, -2, 3, -3, 4, -4, 5, -5];
+        let mut search = PartitioningSearch::new(&set).unwrap();
+        let config = SearchConfig {
+            max_steps: Some(100),
+            ..Default::default()
+        };
+        let mut advances = 1;
+        while !search.advance(&config).unwrap() {
+            assert_eq!(search.steps(), advances * 100);
+            advances += 1;
+        }
+        assert_eq!(advances, 11);
+        assert_eq!(search.steps(), search.total_steps());
+        let partitionings = search.finish();
+        assert!(partitionings.is_proven_optimal());
+        assert_eq!(
+            partitionings.collect::<Vec<_>>(),
+            ZeroSumPartitioningIndices::new(&set)
+                .unwrap()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_dp_matches_exhaustive() {
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+        for len in 0..=10 {
+            for max in [3, 10, 1000] {
+                let set = random_values(&mut seed, len, max);
+                let expected = sorted(exhaustive(&set));
+                assert_eq!(
+                    count_longest_zero_sum_partitionings(&set).unwrap(),
+                    expected.len() as u64,
+                );
+                assert_eq!(
+                    canonical(&set, longest_zero_sum_partitionings_dp(&set).unwrap()),
+                    expected,
+                    "{set:?}",
+                );
+            }
+        }
+    }
+}
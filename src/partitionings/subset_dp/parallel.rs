@@ -0,0 +1,202 @@
+use rayon::prelude::*;
+
+use super::{PartitioningSearch, SubsetTable, ZeroSumPartitioningIndices};
+use crate::partitionings::{check_balanced, Amount, PartitioningError};
+
+/// Number of the highest items whose subsets split the table into blocks.
+///
+/// A block only depends on itself and on blocks for subsets of its highest
+/// items, so blocks for the same number of highest items are filled at once.
+const SPLIT_LEN: usize = 6;
+
+/// Same as [`super::longest_zero_sum_partitionings_dp`] but fills the subset
+/// table across all cores.
+pub fn longest_zero_sum_partitionings_par<T>(
+    set: &[T],
+) -> Result<Vec<Vec<Vec<&T>>>, PartitioningError>
+where
+    T: Amount + Send + Sync,
+{
+    check_balanced(set)?;
+    Ok(ZeroSumPartitioningIndices::new_parallel(set)?
+        .map(|partitioning| {
+            partitioning
+                .into_iter()
+                .map(|partition| partition.into_iter().map(|i| &set[i]).collect())
+                .collect()
+        })
+        .collect())
+}
+
+impl<T: Amount + Send + Sync> ZeroSumPartitioningIndices<T> {
+    /// Same as [`Self::new`] but fills the table across all cores. The table
+    /// is the same as a single threaded one, so the partitionings are yielded
+    /// in the same order.
+    pub fn new_parallel(set: &[T]) -> Result<Self, PartitioningError> {
+        let mut search = PartitioningSearch::new(set)?;
+        search.fill_parallel()?;
+        Ok(search.finish())
+    }
+}
+
+impl<T: Amount + Send + Sync> PartitioningSearch<T> {
+    /// Fills the rest of the table across all cores, without a budget. The
+    /// rows already filled, by advancing or reusing a search, are kept.
+    pub fn fill_parallel(&mut self) -> Result<(), PartitioningError> {
+        self.table.fill_parallel()
+    }
+}
+
+impl<T: Amount + Send + Sync> SubsetTable<T> {
+    fn fill_parallel(&mut self) -> Result<(), PartitioningError> {
+        let split_len = SPLIT_LEN.min(self.values.len());
+        let low_len = self.values.len() - split_len;
+        let block_len = 1 << low_len;
+        for layer in 0..=split_len {
+            // Blocks are filled in ascending order too, so only the blocks
+            // from the first one that isn't filled are left.
+            let highs: Vec<usize> = (0..1_usize << split_len)
+                .filter(|high| high.count_ones() as usize == layer)
+                .filter(|high| (high + 1) << low_len > self.filled)
+                .collect();
+            let blocks = highs
+                .par_iter()
+                .map(|&high| self.block(high, low_len))
+                .collect::<Result<Vec<_>, _>>()?;
+            for (high, (sums, prefixes)) in highs.into_iter().zip(blocks) {
+                let start = high << low_len;
+                self.sums[start..start + block_len].clone_from_slice(&sums);
+                self.prefixes[start..start + block_len].copy_from_slice(&prefixes);
+            }
+        }
+        self.filled = self.sums.len();
+        // The first zero sum mask with the most prefixes, as when filling in
+        // ascending order.
+        let full_mask = self.sums.len() - 1;
+        self.best_mask = (1..full_mask)
+            .filter(|&mask| self.sums[mask].is_zero())
+            .fold(0, |best, mask| {
+                if self.prefixes[mask] > self.prefixes[best] {
+                    mask
+                } else {
+                    best
+                }
+            });
+        Ok(())
+    }
+
+    /// Sums and most zero sum prefixes of the masks whose highest items are
+    /// `high`, given that the blocks for its submasks are filled. The masks of
+    /// the block that are already filled are copied rather than computed.
+    fn block(&self, high: usize, low_len: usize) -> Result<(Vec<T>, Vec<u8>), PartitioningError> {
+        let block_len = 1 << low_len;
+        let start = high << low_len;
+        let filled_len = self.filled.saturating_sub(start).min(block_len);
+        let mut sums: Vec<T> = Vec::with_capacity(block_len);
+        sums.extend_from_slice(&self.sums[start..start + filled_len]);
+        let mut prefixes = Vec::with_capacity(block_len);
+        prefixes.extend_from_slice(&self.prefixes[start..start + filled_len]);
+        for low in filled_len..block_len {
+            let mask = start | low;
+            if mask == 0 {
+                sums.push(T::zero());
+                prefixes.push(0);
+                continue;
+            }
+            let lowest = mask.trailing_zeros() as usize;
+            let rest = mask & (mask - 1);
+            let rest_sum = if low == 0 {
+                &self.sums[rest]
+            } else {
+                &sums[rest - start]
+            };
+            let sum = rest_sum
+                .checked_add(&self.values[lowest])
+                .ok_or(PartitioningError::Overflow)?;
+            let most_prefixes = (0..self.values.len())
+                .filter(|i| (mask >> i) & 1 == 1)
+                .map(|i| {
+                    let submask = mask & !(1 << i);
+                    if i < low_len {
+                        prefixes[submask - start]
+                    } else {
+                        self.prefixes[submask]
+                    }
+                })
+                .max()
+                .unwrap_or(0);
+            prefixes.push(most_prefixes + u8::from(sum.is_zero()));
+            sums.push(sum);
+        }
+        Ok((sums, prefixes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::longest_zero_sum_partitionings_dp;
+    use super::*;
+    use crate::partitionings::SearchConfig;
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_par_matches_single_threaded() {
+        let sets: [&[i32]; 5] = [
+            &[],
+            &[-6, -2, -1, 2, 3, 4],
+            &[1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 6, -6],
+            &[7, -3, -4, 5, -5, 2, 2, -4, 1, -1, 0, 9, -9, 3],
+            &[1, 2, 3],
+        ];
+        for set in sets {
+            assert_eq!(
+                longest_zero_sum_partitionings_par(set),
+                longest_zero_sum_partitionings_dp(set),
+                "{set:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_fill_parallel_keeps_filled_rows() {
+        let set = [7, -3, -4, 5, -5, 2, 2, -4, 1, -1, 0, 9, -9, 3];
+        let config = SearchConfig {
+            max_steps: Some(1000),
+            ..Default::default()
+        };
+        let mut search = PartitioningSearch::new(&set).unwrap();
+        assert!(!search.advance(&config).unwrap());
+        let steps = search.steps();
+        // Mark a filled row, which stays marked if it isn't computed again.
+        search.table.prefixes[steps as usize] = 100;
+        search.fill_parallel().unwrap();
+        assert!(search.is_complete());
+        assert_eq!(search.table.prefixes[steps as usize], 100);
+    }
+
+    #[test]
+    fn test_fill_parallel_after_advancing_matches_single_threaded() {
+        let set = [7, -3, -4, 5, -5, 2, 2, -4, 1, -1, 0, 9, -9, 3];
+        for max_steps in [1, 200, 1000, 5000] {
+            let config = SearchConfig {
+                max_steps: Some(max_steps),
+                ..Default::default()
+            };
+            let mut search = PartitioningSearch::new(&set).unwrap();
+            search.advance(&config).unwrap();
+            search.fill_parallel().unwrap();
+            let partitionings: Vec<_> = search.finish().collect();
+            let expected: Vec<_> = ZeroSumPartitioningIndices::new(&set).unwrap().collect();
+            assert_eq!(partitionings, expected, "{max_steps}");
+        }
+    }
+
+    #[test]
+    fn test_longest_zero_sum_partitionings_par_overflow() {
+        let mut set = vec![1; 8];
+        set.extend([i32::MAX, i32::MAX]);
+        assert_eq!(
+            longest_zero_sum_partitionings_par(&set),
+            Err(PartitioningError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use super::{Amount, PartitioningError, ZeroSumPartitioningIndices};
+
+/// Items that can be swapped with each other without changing how a
+/// partitioning looks, because they have the same non-zero value.
+///
+/// Swapping interchangeable items between partitions gives a partitioning
+/// with the same structure, so only one partitioning of each structure is
+/// canonical: the one where each class of interchangeable items fills the
+/// partitions in order of their sorted values, and then their lowest index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symmetry {
+    /// Rank of each item's value among the distinct values of the set.
+    ranks: Vec<usize>,
+    classes: Vec<Vec<usize>>,
+}
+
+impl Symmetry {
+    pub fn new<T>(set: &[T]) -> Self
+    where
+        T: Amount + Ord,
+    {
+        let mut distinct: Vec<_> = set.iter().collect();
+        distinct.sort();
+        distinct.dedup();
+        let ranks: Vec<_> = set
+            .iter()
+            .map(|item| distinct.binary_search(&item).unwrap_or_default())
+            .collect();
+        let mut classes: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (index, item) in set.iter().enumerate() {
+            if !item.is_zero() {
+                classes.entry(ranks[index]).or_default().push(index);
+            }
+        }
+        let mut classes: Vec<_> = classes
+            .into_values()
+            .filter(|class| class.len() > 1)
+            .collect();
+        classes.sort();
+        Self { ranks, classes }
+    }
+
+    /// Classes of two or more interchangeable items, as indices into the set.
+    pub fn interchangeable(&self) -> &[Vec<usize>] {
+        &self.classes
+    }
+
+    /// Whether the partitioning is the canonical one of those with the same
+    /// structure.
+    pub fn is_canonical(&self, partitioning: &[Vec<usize>]) -> bool {
+        if self.classes.is_empty() {
+            return true;
+        }
+        let keys: Vec<_> = partitioning
+            .iter()
+            .map(|partition| {
+                let mut ranks: Vec<_> = partition.iter().map(|&i| self.ranks[i]).collect();
+                ranks.sort();
+                (ranks, partition.iter().min().copied())
+            })
+            .collect();
+        let mut partition_of = vec![0; self.ranks.len()];
+        for (position, partition) in partitioning.iter().enumerate() {
+            for &i in partition {
+                partition_of[i] = position;
+            }
+        }
+        self.classes.iter().all(|class| {
+            class
+                .windows(2)
+                .all(|pair| keys[partition_of[pair[0]]] <= keys[partition_of[pair[1]]])
+        })
+    }
+}
+
+/// Iterator adaptor that skips partitionings which only differ from an
+/// earlier one by swapping interchangeable items.
+pub struct DistinctPartitionings<I> {
+    symmetry: Symmetry,
+    partitionings: I,
+}
+
+impl<I> DistinctPartitionings<I> {
+    pub fn new(symmetry: Symmetry, partitionings: I) -> Self {
+        Self {
+            symmetry,
+            partitionings,
+        }
+    }
+}
+
+impl<I> Iterator for DistinctPartitionings<I>
+where
+    I: Iterator<Item = Vec<Vec<usize>>>,
+{
+    type Item = Vec<Vec<usize>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let symmetry = &self.symmetry;
+        self.partitionings
+            .find(|partitioning| symmetry.is_canonical(partitioning))
+    }
+}
+
+/// Same as [`super::zero_sum_partitionings_iter`] but yields each structurally
+/// distinct partitioning once, treating items with equal values as
+/// interchangeable.
+pub fn distinct_zero_sum_partitionings_iter<T>(
+    set: &[T],
+) -> Result<impl Iterator<Item = Vec<Vec<&T>>>, PartitioningError>
+where
+    T: Amount + Ord,
+{
+    let partitionings =
+        DistinctPartitionings::new(Symmetry::new(set), ZeroSumPartitioningIndices::new(set)?);
+    Ok(partitionings.map(move |partitioning| {
+        partitioning
+            .into_iter()
+            .map(|partition| partition.into_iter().map(|i| &set[i]).collect())
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distinct_zero_sum_partitionings_iter_equal_amounts() {
+        // Four people each owing 25 to four people each owed 25.
+        let set = [25, 25, 25, 25, -25, -25, -25, -25];
+        assert_eq!(ZeroSumPartitioningIndices::new(&set).unwrap().count(), 24);
+        let partitionings: Vec<_> = distinct_zero_sum_partitionings_iter(&set)
+            .unwrap()
+            .collect();
+        assert_eq!(partitionings.len(), 1);
+        assert_eq!(
+            Symmetry::new(&set).interchangeable(),
+            [vec![0, 1, 2, 3], vec![4, 5, 6, 7]]
+        );
+    }
+
+    #[test]
+    fn test_distinct_zero_sum_partitionings_iter_different_structures() {
+        // Either both 2s pay the 4 and the 3 pays the -1s, or one 2 pays two
+        // of the -1s and the rest form the other partition.
+        let set = [2, 2, -4, 3, -1, -1, -1];
+        let all: Vec<_> = ZeroSumPartitioningIndices::new(&set).unwrap().collect();
+        let distinct: Vec<_> = distinct_zero_sum_partitionings_iter(&set)
+            .unwrap()
+            .collect();
+        assert_eq!(all.len(), 7);
+        assert_eq!(distinct.len(), 2);
+        let set = [1, 1, 2, -2, -2];
+        let all: Vec<_> = ZeroSumPartitioningIndices::new(&set).unwrap().collect();
+        let distinct: Vec<_> = distinct_zero_sum_partitionings_iter(&set)
+            .unwrap()
+            .collect();
+        assert_eq!(all.len(), 2);
+        assert_eq!(distinct, [vec![vec![&1, &1, &-2], vec![&2, &-2]]]);
+    }
+
+    #[test]
+    fn test_is_canonical_without_interchangeable_items() {
+        let set = [-6, -2, -1, 2, 3, 4];
+        let symmetry = Symmetry::new(&set);
+        assert!(symmetry.interchangeable().is_empty());
+        assert!(ZeroSumPartitioningIndices::new(&set)
+            .unwrap()
+            .all(|partitioning| symmetry.is_canonical(&partitioning)));
+    }
+}
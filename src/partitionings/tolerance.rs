@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use super::exhaustive::{subset_sums, ExhaustivePartitionings};
+use super::PartitioningError;
+
+/// Who makes up the difference when a partition doesn't sum to exactly zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Leftover {
+    /// The item at this index, if it is in the partition, and otherwise the
+    /// item with the largest magnitude in the partition.
+    AssignTo(usize),
+    /// Every item in the partition, as evenly as possible.
+    Spread,
+}
+
+/// Returns the amounts to add to items of the partition so that it sums to
+/// exactly zero, as pairs of set index and amount.
+pub fn leftover_adjustments(
+    values: &[i64],
+    partition: &[usize],
+    leftover: Leftover,
+) -> Vec<(usize, i64)> {
+    let sum: i64 = partition.iter().map(|&i| values[i]).sum();
+    if sum == 0 || partition.is_empty() {
+        return vec![];
+    }
+    match leftover {
+        Leftover::AssignTo(index) if partition.contains(&index) => vec![(index, -sum)],
+        Leftover::AssignTo(_) => {
+            let largest = partition
+                .iter()
+                .copied()
+                .min_by_key(|&i| (-values[i].abs(), i))
+                .unwrap();
+            vec![(largest, -sum)]
+        }
+        Leftover::Spread => {
+            let len = partition.len() as i64;
+            // The first items take the cents that don't divide evenly.
+            let (share, extra) = (-sum / len, -sum % len);
+            let mut indices = partition.to_vec();
+            indices.sort();
+            indices
+                .into_iter()
+                .enumerate()
+                .map(|(n, i)| {
+                    (
+                        i,
+                        share
+                            + if (n as i64) < extra.abs() {
+                                extra.signum()
+                            } else {
+                                0
+                            },
+                    )
+                })
+                .filter(|&(_, adjustment)| adjustment != 0)
+                .collect()
+        }
+    }
+}
+
+/// Same as [`leftover_adjustments`] for every partition of a partitioning.
+pub fn partitioning_adjustments(
+    values: &[i64],
+    partitioning: &[Vec<usize>],
+    leftover: Leftover,
+) -> Vec<(usize, i64)> {
+    partitioning
+        .iter()
+        .flat_map(|partition| leftover_adjustments(values, partition, leftover))
+        .collect()
+}
+
+/// Returns an iterator over the partitionings with the most partitions such
+/// that each partition sums to within `tolerance` of zero, yielding indices
+/// into the set.
+///
+/// Amounts are in cents, so a tolerance of a few cents absorbs rounding on
+/// receipts. The leftover of each partition can then be settled with
+/// [`leftover_adjustments`].
+pub fn tolerant_zero_sum_partitionings(
+    set: &[i64],
+    tolerance: i64,
+) -> Result<ExhaustivePartitionings, PartitioningError> {
+    let (zero_indices, indices): (Vec<_>, Vec<_>) =
+        (0..set.len()).partition(|&index| set[index] == 0);
+    let values: Vec<_> = indices.iter().map(|&index| set[index]).collect();
+    let balanced = subset_sums(&values)?
+        .into_iter()
+        .map(|sum| sum.abs() <= tolerance.abs())
+        .collect();
+    ExhaustivePartitionings::new(indices, zero_indices, balanced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tolerant_zero_sum_partitionings_rounding() {
+        // Three receipts each split with a cent lost to rounding.
+        let set = [333, -334, 500, -499, 250, -251];
+        assert_eq!(tolerant_zero_sum_partitionings(&set, 0).unwrap().count(), 0);
+        let mut partitionings = tolerant_zero_sum_partitionings(&set, 1).unwrap();
+        assert_eq!(partitionings.total_len(), 1);
+        let partitioning = partitionings.next().unwrap();
+        assert_eq!(partitioning, [vec![0, 1], vec![2, 3], vec![4, 5]]);
+        assert_eq!(partitionings.next(), None);
+        assert_eq!(
+            partitioning_adjustments(&set, &partitioning, Leftover::Spread),
+            [(0, 1), (2, -1), (4, 1)]
+        );
+    }
+
+    #[test]
+    fn test_tolerant_zero_sum_partitionings_exact() {
+        let set = [-6, -2, -1, 2, 3, 4, 0];
+        let partitionings: Vec<_> = tolerant_zero_sum_partitionings(&set, 0).unwrap().collect();
+        assert_eq!(partitionings.len(), 2);
+        assert!(partitionings
+            .iter()
+            .all(|partitioning| partitioning.len() == 3));
+        assert_eq!(
+            tolerant_zero_sum_partitionings(&[0, 0], 5)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [vec![vec![0], vec![1]]]
+        );
+    }
+
+    #[test]
+    fn test_leftover_adjustments() {
+        let values = [1000, -995, -2];
+        assert_eq!(
+            leftover_adjustments(&values, &[0, 1, 2], Leftover::AssignTo(2)),
+            [(2, -3)]
+        );
+        assert_eq!(
+            leftover_adjustments(&values, &[0, 1, 2], Leftover::AssignTo(5)),
+            [(0, -3)]
+        );
+        assert_eq!(
+            leftover_adjustments(&values, &[0, 1, 2], Leftover::Spread),
+            [(0, -1), (1, -1), (2, -1)]
+        );
+        assert_eq!(
+            leftover_adjustments(&[5, -1], &[0, 1], Leftover::Spread),
+            [(0, -2), (1, -2)]
+        );
+        assert_eq!(
+            leftover_adjustments(&[6, -1], &[0, 1], Leftover::Spread),
+            [(0, -3), (1, -2)]
+        );
+    }
+}
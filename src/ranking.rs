@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::balancing::Transaction;
+
+/// Secondary objective for ordering partitionings that have the same number
+/// of partitions, where a lower score is better.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Objective {
+    /// Total amount of money changing hands.
+    #[default]
+    TotalMoved,
+    /// Value of the largest single transfer.
+    LargestTransfer,
+    /// Most transfers that any one person is part of.
+    TransfersPerPerson,
+}
+
+impl Objective {
+    pub const ALL: [Self; 3] = [
+        Self::TotalMoved,
+        Self::LargestTransfer,
+        Self::TransfersPerPerson,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::TotalMoved => "Least money moved",
+            Self::LargestTransfer => "Smallest largest transfer",
+            Self::TransfersPerPerson => "Fewest transfers per person",
+        }
+    }
+
+    pub fn score(&self, transactions: &[Transaction]) -> u64 {
+        match self {
+            Self::TotalMoved => transactions
+                .iter()
+                .map(|transaction| transaction.value as u64)
+                .sum(),
+            Self::LargestTransfer => transactions
+                .iter()
+                .map(|transaction| transaction.value as u64)
+                .max()
+                .unwrap_or(0),
+            Self::TransfersPerPerson => {
+                let mut counts: HashMap<&str, u64> = HashMap::new();
+                for transaction in transactions {
+                    *counts.entry(&transaction.source).or_default() += 1;
+                    *counts.entry(&transaction.destination).or_default() += 1;
+                }
+                counts.into_values().max().unwrap_or(0)
+            }
+        }
+    }
+
+    /// Scores by this objective, then by the others in order so that ties
+    /// are broken the same way every time.
+    fn scores(&self, transactions: &[Transaction]) -> [u64; 3] {
+        let mut scores = [self.score(transactions), 0, 0];
+        for (score, objective) in scores[1..]
+            .iter_mut()
+            .zip(Self::ALL.iter().filter(|&objective| objective != self))
+        {
+            *score = objective.score(transactions);
+        }
+        scores
+    }
+}
+
+/// Returns the order of the pages of transactions from best to worst by the
+/// objective, as indices into `pages`. Pages that tie on every objective keep
+/// their original order.
+pub fn rank(pages: &[Vec<Transaction>], objective: Objective) -> Vec<usize> {
+    let scores: Vec<_> = pages
+        .iter()
+        .map(|transactions| objective.scores(transactions))
+        .collect();
+    let mut order: Vec<_> = (0..pages.len()).collect();
+    order.sort_by_key(|&i| scores[i]);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let pages = [
+            vec![
+                Transaction::from("a", "b", 500),
+                Transaction::from("c", "b", 500),
+            ],
+            vec![
+                Transaction::from("a", "b", 300),
+                Transaction::from("c", "d", 600),
+            ],
+            vec![
+                Transaction::from("a", "b", 400),
+                Transaction::from("c", "d", 400),
+                Transaction::from("e", "f", 200),
+            ],
+        ];
+        // The first and third pages tie on money moved, and the third has
+        // the smaller largest transfer.
+        assert_eq!(rank(&pages, Objective::TotalMoved), [1, 2, 0]);
+        assert_eq!(rank(&pages, Objective::LargestTransfer), [2, 0, 1]);
+        // The second and third pages tie on transfers per person, and the
+        // second moves less money.
+        assert_eq!(rank(&pages, Objective::TransfersPerPerson), [1, 2, 0]);
+    }
+}
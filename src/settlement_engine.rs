@@ -0,0 +1,103 @@
+use crate::debt::Debt;
+use crate::partitionings::{
+    PartitioningError, PartitioningSearch, SearchConfig, ZeroSumPartitioningIndices,
+};
+
+/// Starts partition searches for the debts, keeping the subset table of the
+/// last complete search so that adding, removing or editing a single debt
+/// only fills in the subsets containing the changed amount.
+#[derive(Default)]
+pub struct SettlementEngine {
+    previous: Option<PartitioningSearch<i32>>,
+}
+
+impl SettlementEngine {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts a search of the debts, reusing the subsets of the amounts they
+    /// share with the last complete search.
+    pub fn search(&self, debts: &[Debt]) -> Result<PartitioningSearch<i32>, PartitioningError> {
+        let amounts: Vec<_> = debts.iter().map(|debt| debt.value).collect();
+        match &self.previous {
+            Some(previous) => PartitioningSearch::reusing(&amounts, previous),
+            None => PartitioningSearch::new(&amounts),
+        }
+    }
+
+    /// Keeps the table of the search for later ones if it is complete.
+    pub fn remember(&mut self, search: &PartitioningSearch<i32>) {
+        if search.is_complete() {
+            self.previous = Some(search.clone());
+        }
+    }
+
+    /// Searches the debts to completion, returning their partitionings with
+    /// the most zero sum partitions.
+    pub fn partitionings(
+        &mut self,
+        debts: &[Debt],
+    ) -> Result<ZeroSumPartitioningIndices<i32>, PartitioningError> {
+        let mut search = self.search(debts)?;
+        search.advance(&SearchConfig::default())?;
+        self.remember(&search);
+        Ok(search.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balancing::debts;
+
+    /// Partitionings in an order that doesn't depend on how they were found.
+    fn sorted(partitionings: ZeroSumPartitioningIndices<i32>) -> Vec<Vec<Vec<usize>>> {
+        let mut partitionings: Vec<_> = partitionings
+            .map(|mut partitioning| {
+                partitioning.sort();
+                partitioning
+            })
+            .collect();
+        partitionings.sort();
+        partitionings
+    }
+
+    #[test]
+    fn test_settlement_engine_matches_full_recomputation() {
+        let steps: [&[i32]; 8] = [
+            &[-6, -2, -1, 2, 3, 4],
+            // Add an entry.
+            &[-6, -2, -1, 2, 3, 4, 0],
+            &[-6, -2, -1, 2, 3, 4, 5],
+            &[-6, -2, -1, 2, 3, 4, -5],
+            // Edit an entry.
+            &[-6, -2, -1, 2, 3, 5, -5],
+            &[-6, -2, -1, 2, -4, 4, 1, 6],
+            // Remove an entry.
+            &[-6, -1, 2, -4, 4, 1, 6],
+            &[-1, 2, 4, -4, 1, -2],
+        ];
+        let mut engine = SettlementEngine::new();
+        for values in steps {
+            let debts = debts(values);
+            let full = ZeroSumPartitioningIndices::new(values).unwrap();
+            let incremental = engine.partitionings(&debts).unwrap();
+            assert_eq!(incremental.total_len(), full.total_len(), "{values:?}");
+            assert!(incremental.is_proven_optimal());
+            assert_eq!(sorted(incremental), sorted(full), "{values:?}");
+        }
+    }
+
+    #[test]
+    fn test_settlement_engine_reuses_shared_subsets() {
+        let mut engine = SettlementEngine::new();
+        engine
+            .partitionings(&debts(&[-6, -2, -1, 2, 3, 4]))
+            .unwrap();
+        // Only the subsets containing the edited amount are left to fill.
+        let search = engine.search(&debts(&[-6, -2, -1, 2, 3, 5])).unwrap();
+        assert_eq!(search.steps(), (1 << 5) - 1);
+        assert_eq!(search.total_steps(), (1 << 6) - 1);
+    }
+}
@@ -0,0 +1,423 @@
+use gloo_timers::callback::Timeout;
+use serde::{Deserialize, Serialize};
+use yew_agent::{HandlerId, Private, WorkerLink};
+
+use std::iter;
+use std::rc::Rc;
+
+use crate::balancing::{
+    BalancingStrategy, DebtedAmountsAsc, FeeModel, MinFees, MinLargestTransfer, StrategyRegistry,
+    Treasurer, DEFAULT_STRATEGY,
+};
+use crate::debt::Debt;
+use crate::partitionings::{
+    check_balanced, heuristic_zero_sum_partitioning_indices, leftover_adjustments,
+    tolerant_zero_sum_partitionings, DistinctPartitionings, ExhaustivePartitionings, Leftover,
+    PartitioningError, PartitioningSearch, SearchConfig, Symmetry, ZeroSumPartitioningIndices,
+    MAX_EXHAUSTIVE_LEN,
+};
+use crate::ranking::Objective;
+use crate::settlement_engine::SettlementEngine;
+use crate::transaction_pages::{Partitionings, TransactionPage, TransactionPages};
+
+/// Number of subsets the partition search may visit before settling for the
+/// best partitioning found so far.
+const SEARCH_MAX_STEPS: u64 = 1 << 20;
+
+/// Number of subsets visited between progress reports, giving newer inputs a
+/// chance to replace the computation in between.
+const SEARCH_CHUNK_STEPS: u64 = 1 << 14;
+
+/// Number of partitionings above which symmetric ones aren't collapsed, since
+/// counting the distinct ones means visiting all of them.
+const COLLAPSE_MAX_LEN: u64 = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementOptions {
+    /// Number of people with outstanding debts above which the heuristic
+    /// partitioner is used instead of an exact search.
+    pub heuristic_threshold: usize,
+    /// Whether partitionings that only differ by swapping people with equal
+    /// amounts are shown once.
+    pub collapse_symmetric: bool,
+    /// Number of cents that the debts, and each group settling among
+    /// themselves, may be off by.
+    pub tolerance: i64,
+    /// Who makes up the difference when the debts are off by less than the
+    /// tolerance.
+    pub leftover: Leftover,
+    /// Objective to show the best partitionings by first, or `None` for the
+    /// order they are found in.
+    pub objective: Option<Objective>,
+    /// Name of the balancing strategy that settles each group, falling back
+    /// to the default one if it isn't registered.
+    pub strategy: String,
+    /// Person that the treasurer strategy pays everything through, or `None`
+    /// to choose the hub like the spoke hub strategy.
+    pub treasurer: Option<String>,
+    /// Fees of transfers between people that the least fees strategy pays
+    /// the least of.
+    pub fees: FeeModel,
+    /// Most that the smallest largest transfer strategy sends in one payment,
+    /// splitting larger ones, or `None` for no cap.
+    pub cap: Option<u32>,
+}
+
+impl Default for SettlementOptions {
+    fn default() -> Self {
+        Self {
+            heuristic_threshold: 20,
+            collapse_symmetric: true,
+            tolerance: 5,
+            leftover: Leftover::Spread,
+            objective: Some(Objective::default()),
+            strategy: DEFAULT_STRATEGY.to_string(),
+            treasurer: None,
+            fees: FeeModel::default(),
+            cap: None,
+        }
+    }
+}
+
+/// Requests to the worker, tagged with the generation of the entries they are
+/// for so that outputs for stale entries can be told apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SettlementInput {
+    /// Starts settling the debts, replacing any computation in progress.
+    Settle {
+        generation: u64,
+        debts: Vec<Debt>,
+        options: Box<SettlementOptions>,
+    },
+    /// Requests a page of the most recently settled debts.
+    Page { generation: u64, index: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SettlementOutput {
+    Progress {
+        generation: u64,
+        steps: u64,
+        total_steps: u64,
+    },
+    Page {
+        generation: u64,
+        page: TransactionPage,
+    },
+}
+
+pub enum SettlementMessage {
+    /// Continues the search for the given generation.
+    Continue(u64),
+}
+
+enum Job {
+    Searching {
+        debts: Vec<Debt>,
+        options: SettlementOptions,
+        /// Amounts added to the debts before searching, as pairs of debt
+        /// index and amount.
+        adjustments: Vec<(usize, i64)>,
+        search: PartitioningSearch<i32>,
+    },
+    Settled(TransactionPages),
+}
+
+/// Web worker that searches for partitionings and balances them off the main
+/// thread.
+///
+/// The search runs in chunks scheduled through the event loop, so a newer
+/// `Settle` input replaces the search in progress instead of waiting for it.
+pub struct SettlementWorker {
+    link: WorkerLink<Self>,
+    handler_id: Option<HandlerId>,
+    generation: u64,
+    job: Option<Job>,
+    /// Keeps the work of the last complete search for the next entries.
+    engine: SettlementEngine,
+    strategies: StrategyRegistry,
+    /// Strategy of the most recent settlement.
+    strategy: Rc<dyn BalancingStrategy>,
+}
+
+impl SettlementWorker {
+    fn respond(&self, output: SettlementOutput) {
+        if let Some(id) = self.handler_id {
+            self.link.respond(id, output);
+        }
+    }
+
+    /// Schedules the next chunk of the search after pending inputs have been
+    /// handled.
+    fn schedule(&self) {
+        let link = self.link.clone();
+        let generation = self.generation;
+        Timeout::new(0, move || {
+            link.send_message(SettlementMessage::Continue(generation))
+        })
+        .forget();
+    }
+
+    /// Replaces the job with the settled pages and responds with the first.
+    fn settle(&mut self, pages: TransactionPages) {
+        self.job = Some(Job::Settled(pages.with_strategy(self.strategy.clone())));
+        self.respond_page(0);
+    }
+
+    fn respond_page(&mut self, index: usize) {
+        if let Some(Job::Settled(pages)) = &mut self.job {
+            let page = pages.page(index);
+            self.respond(SettlementOutput::Page {
+                generation: self.generation,
+                page,
+            });
+        }
+    }
+}
+
+/// Amounts of the debts to partition, in the same order.
+fn amounts(debts: &[Debt]) -> Vec<i32> {
+    debts.iter().map(|debt| debt.value).collect()
+}
+
+/// Partitionings that can be restarted after counting the distinct ones.
+trait Rewind: Iterator<Item = Vec<Vec<usize>>> + 'static {
+    fn rewind(&mut self);
+}
+
+impl Rewind for ZeroSumPartitioningIndices<i32> {
+    fn rewind(&mut self) {
+        ZeroSumPartitioningIndices::rewind(self);
+    }
+}
+
+impl Rewind for ExhaustivePartitionings {
+    fn rewind(&mut self) {
+        ExhaustivePartitionings::rewind(self);
+    }
+}
+
+/// Pages for the `len` partitionings of a finished search, ranked by the
+/// objective of the options.
+fn settled_pages<P: Rewind>(
+    debts: Vec<Debt>,
+    options: &SettlementOptions,
+    partitionings: P,
+    len: u64,
+    proven_optimal: bool,
+) -> TransactionPages {
+    let pages = collapsed_pages(debts, options, partitionings, len, proven_optimal);
+    match options.objective {
+        Some(objective) => pages.with_objective(objective),
+        None => pages,
+    }
+}
+
+/// Pages for the `len` partitionings of a finished search, collapsing those
+/// that only differ by swapping people with equal amounts if requested.
+fn collapsed_pages<P: Rewind>(
+    debts: Vec<Debt>,
+    options: &SettlementOptions,
+    mut partitionings: P,
+    len: u64,
+    proven_optimal: bool,
+) -> TransactionPages {
+    let symmetry = Symmetry::new(&amounts(&debts));
+    if !options.collapse_symmetric
+        || symmetry.interchangeable().is_empty()
+        || len > COLLAPSE_MAX_LEN
+    {
+        return TransactionPages::new(debts, Box::new(partitionings), len, proven_optimal);
+    }
+    let distinct_len = partitionings
+        .by_ref()
+        .filter(|partitioning| symmetry.is_canonical(partitioning))
+        .count() as u64;
+    partitionings.rewind();
+    let interchangeable = symmetry.interchangeable().to_vec();
+    let partitionings: Partitionings =
+        Box::new(DistinctPartitionings::new(symmetry, partitionings));
+    TransactionPages::new(debts, partitionings, distinct_len, proven_optimal)
+        .with_interchangeable(interchangeable)
+}
+
+impl yew_agent::Worker for SettlementWorker {
+    type Reach = Private<Self>;
+    type Message = SettlementMessage;
+    type Input = SettlementInput;
+    type Output = SettlementOutput;
+
+    fn create(link: WorkerLink<Self>) -> Self {
+        Self {
+            link,
+            handler_id: None,
+            generation: 0,
+            job: None,
+            engine: SettlementEngine::new(),
+            strategies: StrategyRegistry::default(),
+            strategy: Rc::new(DebtedAmountsAsc),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) {
+        match msg {
+            SettlementMessage::Continue(generation) if generation == self.generation => {
+                let Some(Job::Searching { search, .. }) = &mut self.job else {
+                    return;
+                };
+                let remaining_steps = SEARCH_MAX_STEPS.saturating_sub(search.steps());
+                let config = SearchConfig {
+                    max_steps: Some(SEARCH_CHUNK_STEPS.min(remaining_steps)),
+                    ..Default::default()
+                };
+                let complete = match search.advance(&config) {
+                    Ok(complete) => complete,
+                    Err(error) => {
+                        log::warn!("{}", error);
+                        if let Some(Job::Searching { debts, .. }) = self.job.take() {
+                            self.settle(TransactionPages::empty(debts));
+                        }
+                        return;
+                    }
+                };
+                let (steps, total_steps) = (search.steps(), search.total_steps());
+                if complete || steps >= SEARCH_MAX_STEPS {
+                    if let Some(Job::Searching {
+                        debts,
+                        options,
+                        adjustments,
+                        search,
+                    }) = self.job.take()
+                    {
+                        self.engine.remember(&search);
+                        let partitionings = search.finish();
+                        let len = partitionings.total_len();
+                        let proven_optimal = partitionings.is_proven_optimal();
+                        let pages =
+                            settled_pages(debts, &options, partitionings, len, proven_optimal)
+                                .with_adjustments(adjustments);
+                        self.settle(pages);
+                    }
+                } else {
+                    self.respond(SettlementOutput::Progress {
+                        generation,
+                        steps,
+                        total_steps: total_steps.min(SEARCH_MAX_STEPS),
+                    });
+                    self.schedule();
+                }
+            }
+            // The search for an older generation has been replaced.
+            SettlementMessage::Continue(_) => {}
+        }
+    }
+
+    fn handle_input(&mut self, msg: Self::Input, id: HandlerId) {
+        self.handler_id = Some(id);
+        match msg {
+            SettlementInput::Settle {
+                generation,
+                mut debts,
+                options,
+            } => {
+                self.generation = generation;
+                self.strategies.register(Rc::new(Treasurer {
+                    name: options.treasurer.clone(),
+                }));
+                self.strategies
+                    .register(Rc::new(MinLargestTransfer { cap: options.cap }));
+                self.strategies.register(Rc::new(MinFees {
+                    fees: options.fees.clone(),
+                }));
+                self.strategy = self.strategies.get_or_default(&options.strategy);
+                let non_zero_len = debts.iter().filter(|debt| debt.value != 0).count();
+                let values: Vec<_> = debts.iter().map(|debt| debt.value as i64).collect();
+                let leftover: i64 = values.iter().sum();
+                let mut adjustments = vec![];
+                if leftover.abs() > options.tolerance {
+                    let mut pages = TransactionPages::empty(debts);
+                    if let Err(PartitioningError::Unbalanced { entries, .. }) =
+                        check_balanced(&values)
+                    {
+                        pages = pages.with_imbalance(leftover, entries);
+                    }
+                    self.settle(pages);
+                    return;
+                }
+                if leftover != 0 && leftover.abs() <= options.tolerance {
+                    if non_zero_len <= MAX_EXHAUSTIVE_LEN {
+                        let pages =
+                            match tolerant_zero_sum_partitionings(&values, options.tolerance) {
+                                Ok(partitionings) => {
+                                    let len = partitionings.total_len();
+                                    settled_pages(debts, &options, partitionings, len, true)
+                                        .with_leftover(options.leftover)
+                                }
+                                Err(error) => {
+                                    log::warn!("{}", error);
+                                    TransactionPages::empty(debts)
+                                }
+                            };
+                        self.settle(pages);
+                        return;
+                    }
+                    // Too many people to search within the tolerance, so the
+                    // whole group makes up the difference before searching.
+                    let everyone: Vec<_> = (0..debts.len()).collect();
+                    adjustments = leftover_adjustments(&values, &everyone, options.leftover);
+                    for &(i, value) in &adjustments {
+                        debts[i].value += value as i32;
+                    }
+                }
+                if non_zero_len > options.heuristic_threshold {
+                    let pages = match heuristic_zero_sum_partitioning_indices(&amounts(&debts)) {
+                        Ok(Some((partitioning, upper_bound))) => {
+                            let proven_optimal = partitioning.len() == upper_bound;
+                            TransactionPages::new(
+                                debts,
+                                Box::new(iter::once(partitioning)),
+                                1,
+                                proven_optimal,
+                            )
+                            .with_adjustments(adjustments)
+                        }
+                        Ok(None) => TransactionPages::empty(debts),
+                        Err(error) => {
+                            log::warn!("{}", error);
+                            TransactionPages::empty(debts)
+                        }
+                    };
+                    self.settle(pages);
+                    return;
+                }
+                match self.engine.search(&debts) {
+                    Ok(search) => {
+                        self.job = Some(Job::Searching {
+                            debts,
+                            options: *options,
+                            adjustments,
+                            search,
+                        });
+                        self.schedule();
+                    }
+                    Err(error) => {
+                        log::warn!("{}", error);
+                        self.settle(TransactionPages::empty(debts));
+                    }
+                }
+            }
+            SettlementInput::Page { generation, index } if generation == self.generation => {
+                self.respond_page(index);
+            }
+            SettlementInput::Page { .. } => {}
+        }
+    }
+
+    fn name_of_resource() -> &'static str {
+        "worker.js"
+    }
+
+    fn resource_path_is_relative() -> bool {
+        true
+    }
+}
@@ -1,7 +1,7 @@
 use std::rc::Rc;
 use yew::prelude::*;
 
-use crate::debt::Debt;
+use payback::debt::Debt;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Entry {
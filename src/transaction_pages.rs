@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::balancing::{BalancingStrategy, DebtedAmountsAsc, Transaction};
+use crate::debt::Debt;
+use crate::partitionings::{partitioning_adjustments, Leftover};
+use crate::ranking::{rank, Objective};
+
+/// Number of partitionings that are balanced up front to rank them, before
+/// the first page is shown. Any beyond this follow in the order they are
+/// found.
+const RANK_MAX_LEN: usize = 64;
+
+/// Partitionings of the debts as indices into them.
+pub type Partitionings = Box<dyn Iterator<Item = Vec<Vec<usize>>>>;
+
+/// Transactions for each of the partitionings with the most zero sum
+/// partitions, computed only as far as the pages that have been visited.
+pub struct TransactionPages {
+    debts: Vec<Debt>,
+    partitionings: Partitionings,
+    pages: Vec<Vec<Transaction>>,
+    page_adjustments: Vec<Vec<Adjustment>>,
+    /// Number of transactions on each page beyond the fewest that settle its
+    /// partitions.
+    page_extra_lens: Vec<usize>,
+    len: u64,
+    proven_optimal: bool,
+    interchangeable: Vec<Vec<usize>>,
+    /// Who makes up the difference in partitions that only sum to within a
+    /// tolerance of zero.
+    leftover: Option<Leftover>,
+    /// Amounts that were added to the debts before partitioning them.
+    adjustments: Vec<(usize, i64)>,
+    /// Objective to order the pages by, until they have been ranked.
+    objective: Option<Objective>,
+    /// Number of pages, from the first, that were ranked by the objective.
+    ranked_len: usize,
+    /// Net amount of debts that don't sum to zero, and the indices of those
+    /// that could be lowered to balance them.
+    imbalance: Option<(i64, Vec<usize>)>,
+    /// Settles each partition of a partitioning.
+    strategy: Rc<dyn BalancingStrategy>,
+}
+
+/// Amount added to a person's debt so that their partition sums to exactly
+/// zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Adjustment {
+    pub name: String,
+    pub value: i32,
+}
+
+impl fmt::Display for Adjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}'s amount adjusted by {}${}.{:02}",
+            self.name,
+            if self.value < 0 { "-" } else { "+" },
+            self.value.unsigned_abs() / 100,
+            self.value.unsigned_abs() % 100,
+        )
+    }
+}
+
+/// Amount by which the debts miss summing to zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Imbalance {
+    pub net: i64,
+    /// Names of people on the side that is over, any of whose amounts could
+    /// be lowered to balance the debts.
+    pub names: Vec<String>,
+}
+
+impl fmt::Display for Imbalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Amounts are ${}.{:02} {}; the group is unbalanced",
+            self.net.unsigned_abs() / 100,
+            self.net.unsigned_abs() % 100,
+            if self.net < 0 { "under" } else { "over" },
+        )
+    }
+}
+
+/// Snapshot of a single page along with what is known about the rest.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TransactionPage {
+    pub index: usize,
+    pub transactions: Vec<Transaction>,
+    /// Total number of pages.
+    pub len: u64,
+    /// Whether the search finished within its budget, so no partitioning has
+    /// fewer transactions than those on the pages.
+    pub proven_optimal: bool,
+    /// Names of people with equal amounts, when partitionings that only differ
+    /// by swapping them are shown once.
+    pub interchangeable: Vec<Vec<String>>,
+    /// Rounding differences made up by people on this page.
+    pub adjustments: Vec<Adjustment>,
+    /// Why there are no pages, if the debts don't sum to zero.
+    pub imbalance: Option<Imbalance>,
+    /// Number of transactions beyond the fewest that settle each partition,
+    /// such as from splitting transfers at a cap.
+    pub extra_len: usize,
+    /// Number of pages, from the first, that are ordered by the objective.
+    /// Any after them follow in the order they were found.
+    pub ranked_len: u64,
+}
+
+impl TransactionPages {
+    /// Creates pages for the given partitionings of the debts, of which there
+    /// are `len`.
+    pub fn new(
+        debts: Vec<Debt>,
+        partitionings: Partitionings,
+        len: u64,
+        proven_optimal: bool,
+    ) -> Self {
+        Self {
+            debts,
+            partitionings,
+            pages: vec![],
+            page_adjustments: vec![],
+            page_extra_lens: vec![],
+            len,
+            proven_optimal,
+            interchangeable: vec![],
+            leftover: None,
+            adjustments: vec![],
+            objective: None,
+            ranked_len: 0,
+            imbalance: None,
+            strategy: Rc::new(DebtedAmountsAsc),
+        }
+    }
+
+    /// Settles each partition with the strategy rather than
+    /// [`DebtedAmountsAsc`].
+    pub fn with_strategy(mut self, strategy: Rc<dyn BalancingStrategy>) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Notes that the debts are off by `net` in total, with the indices of
+    /// the debts that could be lowered to balance them.
+    pub fn with_imbalance(mut self, net: i64, entries: Vec<usize>) -> Self {
+        self.imbalance = Some((net, entries));
+        self
+    }
+
+    /// Orders the pages from best to worst by the objective.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = Some(objective);
+        self
+    }
+
+    /// Makes up the difference of partitions that don't sum to exactly zero
+    /// according to `leftover`.
+    pub fn with_leftover(mut self, leftover: Leftover) -> Self {
+        self.leftover = Some(leftover);
+        self
+    }
+
+    /// Notes amounts, as pairs of debt index and amount, that were already
+    /// added to the debts so that they sum to zero.
+    pub fn with_adjustments(mut self, adjustments: Vec<(usize, i64)>) -> Self {
+        self.adjustments = adjustments;
+        self
+    }
+
+    /// Notes classes of debts, as indices, whose swapped partitionings have
+    /// been collapsed into one.
+    pub fn with_interchangeable(mut self, interchangeable: Vec<Vec<usize>>) -> Self {
+        self.interchangeable = interchangeable;
+        self
+    }
+
+    /// Creates pages for debts that couldn't be partitioned.
+    pub fn empty(debts: Vec<Debt>) -> Self {
+        Self::new(debts, Box::new(std::iter::empty()), 0, true)
+    }
+
+    /// Returns the transactions of the page at `index`, computing it and any
+    /// pages before it if they haven't been visited yet.
+    pub fn get(&mut self, index: usize) -> Option<&Vec<Transaction>> {
+        if let Some(objective) = self.objective.take() {
+            while self.pages.len() < RANK_MAX_LEN && self.push_next().is_some() {}
+            self.ranked_len = self.pages.len();
+            let order = rank(&self.pages, objective);
+            let mut pages: Vec<_> = self.pages.drain(..).map(Some).collect();
+            let mut page_adjustments: Vec<_> = self.page_adjustments.drain(..).map(Some).collect();
+            let page_extra_lens = std::mem::take(&mut self.page_extra_lens);
+            for i in order {
+                self.pages.extend(pages[i].take());
+                self.page_adjustments.extend(page_adjustments[i].take());
+                self.page_extra_lens.push(page_extra_lens[i]);
+            }
+        }
+        while self.pages.len() <= index {
+            self.push_next()?;
+        }
+        self.pages.get(index)
+    }
+
+    /// Balances the next partitioning into a page.
+    fn push_next(&mut self) -> Option<()> {
+        let partitioning = self.partitionings.next()?;
+        let leftover_adjustments = match self.leftover {
+            Some(leftover) => {
+                let values: Vec<_> = self.debts.iter().map(|debt| debt.value as i64).collect();
+                partitioning_adjustments(&values, &partitioning, leftover)
+            }
+            None => vec![],
+        };
+        let mut debts = self.debts.clone();
+        for &(i, value) in &leftover_adjustments {
+            debts[i].value += value as i32;
+        }
+        // A partition that can't be split further needs a transaction for
+        // each person in it with an outstanding debt but one.
+        let fewest_len: usize = partitioning
+            .iter()
+            .map(|partition| {
+                let non_zero_len = partition.iter().filter(|&&i| debts[i].value != 0).count();
+                non_zero_len.saturating_sub(1)
+            })
+            .sum();
+        let transactions: Vec<_> = partitioning
+            .into_iter()
+            .flat_map(|partition| {
+                let partition: Vec<_> = partition.into_iter().map(|i| &debts[i]).collect();
+                self.strategy.balance(&partition)
+            })
+            .collect();
+        self.page_extra_lens
+            .push(transactions.len().saturating_sub(fewest_len));
+        self.pages.push(transactions);
+        let adjustments = self
+            .adjustments
+            .iter()
+            .chain(&leftover_adjustments)
+            .map(|&(i, value)| Adjustment {
+                name: self.debts[i].name.clone(),
+                value: value as i32,
+            })
+            .collect();
+        self.page_adjustments.push(adjustments);
+        Some(())
+    }
+
+    /// Returns a snapshot of the page at `index`.
+    pub fn page(&mut self, index: usize) -> TransactionPage {
+        TransactionPage {
+            index,
+            transactions: self.get(index).cloned().unwrap_or_default(),
+            len: self.len,
+            proven_optimal: self.proven_optimal,
+            interchangeable: self
+                .interchangeable
+                .iter()
+                .map(|class| class.iter().map(|&i| self.debts[i].name.clone()).collect())
+                .collect(),
+            adjustments: self
+                .page_adjustments
+                .get(index)
+                .cloned()
+                .unwrap_or_default(),
+            extra_len: self.page_extra_lens.get(index).copied().unwrap_or_default(),
+            ranked_len: self.ranked_len as u64,
+            imbalance: self.imbalance.as_ref().map(|(net, entries)| Imbalance {
+                net: *net,
+                names: entries
+                    .iter()
+                    .map(|&i| self.debts[i].name.clone())
+                    .collect(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_pages_rank_only_the_first_pages() {
+        let debts = vec![
+            Debt {
+                name: "a".to_string(),
+                value: 100,
+            },
+            Debt {
+                name: "b".to_string(),
+                value: -100,
+            },
+        ];
+        let len = RANK_MAX_LEN as u64 + 10;
+        let partitionings = std::iter::repeat(vec![vec![0, 1]]).take(len as usize);
+        let mut pages = TransactionPages::new(debts, Box::new(partitionings), len, true)
+            .with_objective(Objective::default());
+        let page = pages.page(0);
+        assert_eq!(page.ranked_len, RANK_MAX_LEN as u64);
+        assert_eq!(page.len, len);
+        // Only the ranked partitionings have been balanced so far.
+        assert_eq!(pages.pages.len(), RANK_MAX_LEN);
+        assert_eq!(
+            pages.page(len as usize - 1).transactions,
+            [Transaction::from("a", "b", 100)]
+        );
+    }
+}